@@ -1,4 +1,5 @@
 crate::re_export! {
+    mod rule;
     mod str;
 }
 pub fn always_true(_: &str) -> bool {
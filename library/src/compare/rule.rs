@@ -0,0 +1,205 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use anyhow::bail;
+use axum::http::Method;
+use serde::Deserialize;
+
+use crate::compare::CompareStr;
+
+/// 同时比较请求方法和路径
+///
+/// 所有 [`CompareStr`] 都可以使用 只比较路径
+pub trait CompareReq: Clone {
+    fn compare_req(&self, method: &Method, path: &str) -> bool;
+}
+
+impl<T: CompareStr> CompareReq for T {
+    fn compare_req(&self, _method: &Method, path: &str) -> bool {
+        self.compare(path)
+    }
+}
+
+/// 路径匹配方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// 完全相同
+    Exact(String),
+    /// 以此开头
+    Prefix(String),
+    /// 按 `/` 分段匹配 `*` 匹配段内任意字符 `**` 匹配任意多段
+    Glob(Vec<String>),
+}
+
+impl Pattern {
+    pub fn matches(&self, path: &str) -> bool {
+        match self {
+            Pattern::Exact(exact) => path == exact,
+            Pattern::Prefix(prefix) => path.starts_with(prefix.as_str()),
+            Pattern::Glob(glob) => {
+                let segments = path.split('/').collect::<Vec<_>>();
+                glob_match(glob, &segments)
+            }
+        }
+    }
+}
+
+fn glob_match(glob: &[String], segments: &[&str]) -> bool {
+    match glob.split_first() {
+        None => segments.is_empty(),
+        Some((first, rest)) if first == "**" => (0..=segments.len()).any(|skip| glob_match(rest, &segments[skip..])),
+        Some((first, rest)) => match segments.split_first() {
+            Some((segment, others)) => wildcard_match(first, segment) && glob_match(rest, others),
+            None => false,
+        },
+    }
+}
+
+/// 段内匹配 `*` 匹配任意多个字符
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let Some((head, tail)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(text) = text.strip_prefix(head) else {
+        return false;
+    };
+    (0..=text.len())
+        .filter(|&i| text.is_char_boundary(i))
+        .any(|i| wildcard_match(tail, &text[i..]))
+}
+
+/// 请求匹配规则 可以从字符串解析, 也可以在配置文件中直接写字符串
+///
+/// ```rust,ignore
+/// "/user/login".parse::<Rule>()?;      // 路径完全相同
+/// "POST /user/login".parse::<Rule>()?; // 限定请求方法
+/// "/public/**".parse::<Rule>()?;       // /public 及其下所有路径
+/// "GET /static/*.html".parse::<Rule>()?;
+/// Rule::prefix("/api/v1");             // 以 /api/v1 开头
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rule {
+    method: Option<Method>,
+    pattern: Pattern,
+}
+
+impl Rule {
+    pub fn exact(path: impl Into<String>) -> Self {
+        Self { method: None, pattern: Pattern::Exact(path.into()) }
+    }
+
+    pub fn prefix(path: impl Into<String>) -> Self {
+        Self { method: None, pattern: Pattern::Prefix(path.into()) }
+    }
+
+    pub fn glob(path: &str) -> Self {
+        Self {
+            method: None,
+            pattern: Pattern::Glob(path.split('/').map(Into::into).collect()),
+        }
+    }
+
+    /// 只匹配该请求方法
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    pub fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method.as_ref().is_none_or(|m| m == method) && self.pattern.matches(path)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (method, path) = match s.trim().split_once(char::is_whitespace) {
+            Some((method, path)) => (Some(method.parse::<Method>()?), path.trim()),
+            None => (None, s.trim()),
+        };
+        if !path.starts_with('/') {
+            bail!("路径规则需要以 / 开头: {s}");
+        }
+        let rule = match path.contains('*') {
+            true => Rule::glob(path),
+            false => Rule::exact(path),
+        };
+        Ok(Self { method, ..rule })
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(method) = &self.method {
+            write!(f, "{method} ")?;
+        }
+        match &self.pattern {
+            Pattern::Exact(path) => f.write_str(path),
+            Pattern::Prefix(path) => write!(f, "{path}*"),
+            Pattern::Glob(glob) => f.write_str(&glob.join("/")),
+        }
+    }
+}
+
+impl CompareReq for Rule {
+    fn compare_req(&self, method: &Method, path: &str) -> bool {
+        self.matches(method, path)
+    }
+}
+
+/// 一组规则 任意一条匹配即可
+///
+/// ```rust,ignore
+/// JwtAuth::<User, _>::new(Rules::parse(&["POST /login", "POST /refresh", "/public/**"])?);
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Rules(Arc<Vec<Rule>>);
+
+impl Rules {
+    pub fn parse(rules: &[&str]) -> anyhow::Result<Self> {
+        rules.iter().map(|rule| rule.parse()).collect()
+    }
+}
+
+impl FromIterator<Rule> for Rules {
+    fn from_iter<I: IntoIterator<Item = Rule>>(iter: I) -> Self {
+        Self(Arc::new(iter.into_iter().collect()))
+    }
+}
+
+impl CompareReq for Rules {
+    fn compare_req(&self, method: &Method, path: &str) -> bool {
+        self.0.iter().any(|rule| rule.matches(method, path))
+    }
+}
+
+#[test]
+fn rule_t() {
+    let rule = |s: &str| s.parse::<Rule>().unwrap();
+    let get = |rule: Rule, path: &str| rule.compare_req(&Method::GET, path);
+
+    assert!(get(rule("/login"), "/login"));
+    assert!(!get(rule("/login"), "/admin/login-history"));
+    assert!(!rule("POST /login").compare_req(&Method::GET, "/login"));
+    assert!(rule("POST /login").compare_req(&Method::POST, "/login"));
+
+    assert!(get(rule("/public/**"), "/public"));
+    assert!(get(rule("/public/**"), "/public/a/b.png"));
+    assert!(!get(rule("/public/**"), "/publicity"));
+    assert!(get(rule("/static/*.html"), "/static/index.html"));
+    assert!(!get(rule("/static/*.html"), "/static/a/index.html"));
+    assert!(get(rule("/**/avatar"), "/user/1/avatar"));
+    assert!(get(Rule::prefix("/api"), "/api-docs"));
+
+    assert!("login".parse::<Rule>().is_err());
+    assert_eq!(rule("POST /public/**").to_string(), "POST /public/**");
+}
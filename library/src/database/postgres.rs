@@ -61,10 +61,7 @@ impl<S: Send + Sync> FromRequestParts<S> for PgConn {
 
     async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
         match parts.extensions.get::<PgPool>() {
-            Some(pool) => match pool.get_owned().await.map(Self) {
-                Ok(conn) => Ok(conn),
-                Err(err) => panic!("获取 Postgres 连接失败: {}", err),
-            },
+            Some(pool) => Ok(Self(pool.get_owned().await?)),
             None => panic!("未设置 Postgres 连接池"),
         }
     }
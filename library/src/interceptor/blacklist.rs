@@ -1,10 +1,9 @@
-use std::net::SocketAddr;
-
-use axum::{async_trait, body::Body, extract::ConnectInfo, http::Request};
+use axum::{async_trait, body::Body, http::Request};
 
 use crate::{
     compare::CompareStr,
     interceptor::{Intercept, Interceptor},
+    middleware::ClientIp,
     reject, res, resp,
 };
 
@@ -24,12 +23,10 @@ impl<T: CompareStr + Sync> Intercept for BlackIp<T> {
     type Context = ();
 
     async fn before(&self, req: &mut Request<Body>) -> resp::Result<Self::Context> {
-        let addr = req
-            .extensions()
-            .get::<ConnectInfo<SocketAddr>>()
-            .ok_or_else(|| res!(400, "获取连接 ip 失败"))?;
-
-        let ip = addr.ip().to_string();
+        // 配置了 ClientIpLayer 时使用代理转发的真实 ip
+        let ip = ClientIp::from_extensions(req.extensions())
+            .ok_or_else(|| res!(400, "获取连接 ip 失败"))?
+            .to_string();
         if self.handler.compare(&ip) {
             return reject!(403, "黑名单 ip 禁止访问");
         }
@@ -0,0 +1,71 @@
+use std::marker::PhantomData;
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    jsonwebtoken::{Jwt, JwtToken},
+    resp::{AppError, ErrorKind, Res},
+};
+
+/// 载荷中的角色和权限
+///
+/// ```rust,ignore
+/// impl Authority for User {
+///     fn roles(&self) -> &[String] {
+///         &self.roles
+///     }
+/// }
+/// ```
+pub trait Authority {
+    fn roles(&self) -> &[String];
+
+    fn scopes(&self) -> &[String] {
+        &[]
+    }
+
+    fn has_role(&self, role: &str) -> bool {
+        self.roles().iter().any(|r| r == role)
+    }
+
+    fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().iter().any(|s| s == scope)
+    }
+}
+
+/// 用于 [`RequireRole`] 的角色
+///
+/// ```rust,ignore
+/// struct Admin;
+/// impl Role for Admin {
+///     const NAME: &'static str = "admin";
+/// }
+/// ```
+pub trait Role {
+    const NAME: &'static str;
+}
+
+/// 提取载荷并要求拥有角色 R 否则返回 403
+///
+/// ```rust,ignore
+/// async fn delete(RequireRole(user, _): RequireRole<User, Admin>) -> Resp<()> {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequireRole<T: JwtToken, R>(pub T, pub PhantomData<R>);
+
+#[async_trait]
+impl<T, R, S> FromRequestParts<S> for RequireRole<T, R>
+where
+    T: JwtToken + Authority + Send + Sync + 'static,
+    R: Role,
+    S: Send + Sync,
+{
+    type Rejection = Res<()>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Jwt(data) = Jwt::<T>::from_request_parts(parts, state).await?;
+        if !data.has_role(R::NAME) {
+            return Err(AppError::new(ErrorKind::Forbidden, format!("权限不足: 需要角色 {}", R::NAME)).into());
+        }
+        Ok(Self(data, PhantomData))
+    }
+}
@@ -1,6 +1,9 @@
-use axum::{async_trait, extract::FromRequestParts, http::request::Parts, response::Response};
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
 
-use crate::jsonwebtoken::{auth_token, JwtToken};
+use crate::{
+    jsonwebtoken::{auth_optional, auth_token, Claims, JwtToken},
+    resp::Res,
+};
 
 #[derive(Debug, Clone)]
 pub struct Jwt<T: JwtToken>(pub T);
@@ -11,11 +14,57 @@ where
     T: JwtToken + Send + Sync + 'static,
     S: Send + Sync,
 {
-    type Rejection = Response;
+    type Rejection = Res<()>;
     async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
         match parts.extensions.remove::<T>() {
             Some(data) => Ok(Self(data)),
-            None => Ok(Self(auth_token(&parts.headers)?)),
+            None => Ok(Self(
+                auth_token::<T>(&parts.headers, &parts.uri, T::sources()).await?.data,
+            )),
+        }
+    }
+}
+
+/// 可选认证 没有携带令牌时为 None, 令牌无效或过期时仍然返回 401
+///
+/// 不要使用 `Option<Jwt<T>>`, 它会把无效令牌也当作 None
+#[derive(Debug, Clone)]
+pub struct MaybeJwt<T: JwtToken>(pub Option<T>);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for MaybeJwt<T>
+where
+    T: JwtToken + Send + Sync + 'static,
+    S: Send + Sync,
+{
+    type Rejection = Res<()>;
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.remove::<T>() {
+            Some(data) => Ok(Self(Some(data))),
+            None => Ok(Self(
+                auth_optional::<T>(&parts.headers, &parts.uri, T::sources())
+                    .await?
+                    .map(|claims| claims.data),
+            )),
+        }
+    }
+}
+
+/// 提取完整的 [`Claims`] 需要 jti、exp 时使用
+#[derive(Debug, Clone)]
+pub struct JwtClaims<T: JwtToken>(pub Claims<T>);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for JwtClaims<T>
+where
+    T: JwtToken + Send + Sync + 'static,
+    S: Send + Sync,
+{
+    type Rejection = Res<()>;
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<Claims<T>>() {
+            Some(claims) => Ok(Self(claims.clone())),
+            None => Ok(Self(auth_token(&parts.headers, &parts.uri, T::sources()).await?)),
         }
     }
 }
@@ -1,17 +1,93 @@
 use std::{
     marker::PhantomData,
+    sync::Arc,
     task::{Context, Poll},
 };
 
-use axum::{body::Body, extract::Request, response::Response};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::SET_COOKIE, HeaderMap, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use chrono::Local;
 use futures_util::future::BoxFuture;
+use serde::Serialize;
+use serde_json::Value;
 use tower::{Layer, Service};
 
 use crate::{
-    compare::{always_false, CompareStr},
-    jsonwebtoken::{auth_token, JwtToken},
+    compare::{always_false, CompareReq},
+    jsonwebtoken::{auth_optional, auth_token, Authority, Claims, CookieOptions, JwtToken, TokenSource},
+    resp::{AppError, ErrorKind},
 };
 
+/// 授权规则 返回 false 时拒绝访问
+type Require<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// 认证通过的载荷 [`JwtAuth`] 写入响应的 Extension, 日志按字段名记录用户身份
+///
+/// 只在读取时才序列化载荷
+#[derive(Clone)]
+pub struct Identity(Arc<IdentityFn>);
+
+/// 字段名 -> 字段值
+type IdentityFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+impl Identity {
+    fn new<T: Serialize + Send + Sync + 'static>(data: T) -> Self {
+        Self(Arc::new(move |field| {
+            match serde_json::to_value(&data).ok()?.get(field)? {
+                Value::String(value) => Some(value.clone()),
+                value => Some(value.to_string()),
+            }
+        }))
+    }
+
+    /// 载荷中字段的值
+    pub fn field(&self, name: &str) -> Option<String> {
+        (self.0)(name)
+    }
+}
+
+/// 滑动续期的新令牌写到哪里
+#[derive(Debug, Clone)]
+pub enum Renew {
+    /// 响应头名字 如 `HeaderName::from_static("x-renewed-token")`
+    Header(HeaderName),
+    /// 写入 cookie 有效时长与令牌一致
+    Cookie(CookieOptions),
+}
+
+impl Renew {
+    fn write(&self, headers: &mut HeaderMap, token: &str, max_age: usize) {
+        match self {
+            Renew::Header(name) => {
+                if let Ok(value) = HeaderValue::from_str(token) {
+                    headers.insert(name.clone(), value);
+                }
+            }
+            Renew::Cookie(options) => {
+                headers.append(SET_COOKIE, options.set(token, max_age).0);
+            }
+        }
+    }
+}
+
+/// 剩余有效期少于 window 秒时续期
+#[derive(Debug, Clone)]
+struct Renewal {
+    window: usize,
+    target: Renew,
+}
+
+impl Renewal {
+    fn due<T>(&self, claims: &Claims<T>) -> bool {
+        let now = Local::now().timestamp() as usize;
+        claims.exp.saturating_sub(now) < self.window
+    }
+}
+
 /// # Examples
 ///
 /// ```rust,ignore
@@ -25,18 +101,85 @@ use crate::{
 ///
 /// JwtAuth::<User, _>::new(Arc::new(String::from("/login")));
 /// JwtAuth::<User, _>::new(Arc::new(vec![String::from("/login")]));
+///
+/// // 上面的字符串是包含匹配 "/login" 也会放行 "/admin/login-history"
+/// // 需要精确、前缀、通配或限定请求方法时使用 Rule
+/// JwtAuth::<User, _>::new("POST /login".parse::<Rule>()?);
+/// JwtAuth::<User, _>::new(Rules::parse(&["POST /login", "/public/**"])?);
+///
+/// // 授权 不满足时返回 403
+/// JwtAuth::<User, _>::default().require(|user| user.phone.starts_with("1"));
+/// JwtAuth::<User, _>::default().require_role("admin");
+///
+/// // 可选认证 没有令牌时直接放行 配合 MaybeJwt 使用
+/// JwtAuth::<User, _>::default().optional();
+///
+/// // 滑动续期 活跃用户的令牌快过期时自动换新
+/// JwtAuth::<User, _>::default().renew(60 * 60 * 24, Renew::Cookie(CookieOptions::default()));
 /// ```
 #[derive(Clone)]
 pub struct JwtAuth<T, A> {
     allow: A,
+    require: Option<Require<T>>,
+    sources: &'static [TokenSource],
+    renew: Option<Renewal>,
+    optional: bool,
     // 幻象数据存储类型不会占内存
     payload: PhantomData<T>,
 }
 
-impl<T: JwtToken, A: CompareStr> JwtAuth<T, A> {
+impl<T: JwtToken, A: CompareReq> JwtAuth<T, A> {
     /// allow 返回 true 时免验证
     pub fn new(allow: A) -> Self {
-        Self { allow, payload: PhantomData }
+        Self {
+            allow,
+            require: None,
+            sources: T::sources(),
+            renew: None,
+            optional: false,
+            payload: PhantomData,
+        }
+    }
+
+    /// 提取令牌的位置 默认为 [`JwtToken::sources`]
+    pub fn sources(mut self, sources: &'static [TokenSource]) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// 可选认证 没有携带令牌时不拦截, 携带了令牌则照常验证并写入 Extension
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// 开启滑动续期 令牌剩余有效期少于 window 秒时
+    /// 在内部服务处理完成后按 target 返回新签发的令牌, 旧令牌在过期前仍然有效
+    pub fn renew(mut self, window: usize, target: Renew) -> Self {
+        self.renew = Some(Renewal { window, target });
+        self
+    }
+
+    /// 认证通过后检查载荷 返回 false 时响应 403
+    pub fn require(mut self, require: impl Fn(&T) -> bool + Send + Sync + 'static) -> Self {
+        self.require = Some(Arc::new(require));
+        self
+    }
+
+    /// 要求载荷拥有角色
+    pub fn require_role(self, role: &'static str) -> Self
+    where
+        T: Authority,
+    {
+        self.require(move |data| data.has_role(role))
+    }
+
+    /// 要求载荷拥有权限
+    pub fn require_scope(self, scope: &'static str) -> Self
+    where
+        T: Authority,
+    {
+        self.require(move |data| data.has_scope(scope))
     }
 }
 
@@ -46,11 +189,19 @@ impl<T: JwtToken> Default for JwtAuth<T, fn(&str) -> bool> {
     }
 }
 
-impl<S, T: JwtToken, A: CompareStr> Layer<S> for JwtAuth<T, A> {
+impl<S, T: JwtToken, A: CompareReq> Layer<S> for JwtAuth<T, A> {
     type Service = JwtAuthService<S, T, A>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        JwtAuthService { inner, allow: self.allow.clone(), payload: self.payload }
+        JwtAuthService {
+            inner,
+            allow: self.allow.clone(),
+            require: self.require.clone(),
+            sources: self.sources,
+            renew: self.renew.clone(),
+            optional: self.optional,
+            payload: self.payload,
+        }
     }
 }
 
@@ -58,15 +209,19 @@ impl<S, T: JwtToken, A: CompareStr> Layer<S> for JwtAuth<T, A> {
 pub struct JwtAuthService<S, T, A> {
     inner: S,
     allow: A,
+    require: Option<Require<T>>,
+    sources: &'static [TokenSource],
+    renew: Option<Renewal>,
+    optional: bool,
     payload: PhantomData<T>,
 }
 
 impl<S, T, A> Service<Request<Body>> for JwtAuthService<S, T, A>
 where
-    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
     S::Future: Send + 'static,
     T: JwtToken + Sync + Send + 'static,
-    A: CompareStr,
+    A: CompareReq,
 {
     type Response = S::Response;
     type Error = S::Error;
@@ -78,20 +233,89 @@ where
 
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         // 免验证直接放行
-        if self.allow.compare(req.uri().path()) {
+        if self.allow.compare_req(req.method(), req.uri().path()) {
             return Box::pin(self.inner.call(req));
         }
 
-        let result = auth_token::<T>(req.headers()).map(|claims| {
-            req.extensions_mut().insert(claims);
-            self.inner.call(req)
-        });
+        let require = self.require.clone();
+        let sources = self.sources;
+        let renew = self.renew.clone();
+        let optional = self.optional;
+        let not_ready_inner = self.inner.clone();
+        let mut ready_inner = std::mem::replace(&mut self.inner, not_ready_inner);
 
         Box::pin(async move {
-            match result {
-                Ok(future) => future.await,
-                Err(response) => Ok(response),
+            let claims = match optional {
+                true => auth_optional::<T>(req.headers(), req.uri(), sources).await,
+                false => auth_token::<T>(req.headers(), req.uri(), sources).await.map(Some),
+            };
+            match claims {
+                Ok(None) => ready_inner.call(req).await,
+                Ok(Some(claims)) if require.as_ref().is_some_and(|f| !f(&claims.data)) => {
+                    Ok(AppError::from(ErrorKind::Forbidden).into_response())
+                }
+                Ok(Some(claims)) => {
+                    // 续期失败不影响本次请求 客户端继续使用旧令牌
+                    let renewed = renew
+                        .filter(|renewal| renewal.due(&claims))
+                        .and_then(|renewal| Some((claims.reissue().ok()?, renewal.target)));
+                    let identity = Identity::new(claims.data.clone());
+                    req.extensions_mut().insert(claims.data.clone());
+                    req.extensions_mut().insert(claims);
+                    let mut res = ready_inner.call(req).await?;
+                    res.extensions_mut().insert(identity);
+                    if let Some((token, target)) = renewed {
+                        target.write(res.headers_mut(), &token, T::duration());
+                    }
+                    Ok(res)
+                }
+                Err(err) => Ok(err.into_response()),
             }
         })
     }
 }
+
+#[tokio::test]
+async fn renew_t() {
+    use axum::{routing::get, Router};
+    use once_cell::sync::Lazy;
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    use crate::jsonwebtoken::Secret;
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct User;
+
+    impl JwtToken for User {
+        fn secret() -> &'static Secret {
+            static SECRET: Lazy<Secret> = Lazy::new(|| Secret::new("secret"));
+            &SECRET
+        }
+    }
+
+    let name = HeaderName::from_static("x-renewed-token");
+    let token = User.encode().unwrap();
+    let claims = User::decode_claims(&token).unwrap();
+    let renewal = |window| Renewal { window, target: Renew::Header(name.clone()) };
+    assert!(renewal(User::duration() + 60).due(&claims));
+    assert!(!renewal(60).due(&claims));
+
+    // 快过期时在响应头返回新令牌
+    let app = |window| {
+        Router::new()
+            .route("/", get(|| async {}))
+            .layer(JwtAuth::<User, _>::default().renew(window, Renew::Header(name.clone())))
+    };
+    let req = || {
+        Request::builder()
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let res = app(User::duration() + 60).oneshot(req()).await.unwrap();
+    let renewed = res.headers()[&name].to_str().unwrap();
+    assert_ne!(User::decode_claims(renewed).unwrap().jti, claims.jti);
+    let res = app(60).oneshot(req()).await.unwrap();
+    assert!(!res.headers().contains_key(&name));
+}
@@ -23,9 +23,47 @@
 //!     }
 //! }
 //!
+//! // 同时签发 access_token 和 refresh_token
+//! async fn login_pair(VJson(user): VJson<User>) -> Resp<TokenPair> {
+//!     match user.encode_pair().await {
+//!         Ok(pair) => resolve!(201 => pair, "登录成功"),
+//!         Err(err) => reject!(400, "登录失败: {err}"),
+//!     }
+//! }
+//!
+//! // 刷新令牌只能使用一次 每次刷新都会轮换
+//! async fn refresh(token: String) -> Resp<TokenPair> {
+//!     match User::refresh(&token).await {
+//!         Ok(pair) => resolve!(200 => pair, "刷新成功"),
+//!         Err(err) => Err(AppError::new(ErrorKind::Unauthorized, "刷新失败").with_cause(err).into()),
+//!     }
+//! }
+//!
 //! async fn info(Jwt(user): Jwt<User>) -> Resp<User> {
 //!     resolve!(200 => user, "获取用户信息成功")
 //! }
+//!
+//! // 公开接口 登录用户返回个性化内容, 携带了无效令牌依然返回 401
+//! async fn home(MaybeJwt(user): MaybeJwt<User>) -> Resp<String> {
+//!     match user {
+//!         Some(user) => resolve!(200 => format!("你好 {}", user.name), "获取首页成功"),
+//!         None => resolve!(200 => "你好".into(), "获取首页成功"),
+//!     }
+//! }
+//!
+//! // 需要实现 Authority 没有 admin 角色时返回 403
+//! async fn admin(RequireRole(user, _): RequireRole<User, Admin>) -> Resp<User> {
+//!     resolve!(200 => user, "获取用户信息成功")
+//! }
+//!
+//! // 退出登录 作废当前令牌
+//! async fn logout(JwtClaims(claims): JwtClaims<User>) -> Resp<()> {
+//!     match claims.revoke().await {
+//!         Ok(_) => resolve!(200, "退出登录成功"),
+//!         // 原因只写入日志 不返回给客户端
+//!         Err(err) => Err(AppError::new(ErrorKind::Internal, "退出登录失败").with_cause(err).into()),
+//!     }
+//! }
 //! ```
 //!
 //! # 中间件
@@ -41,75 +79,403 @@
 //!
 //! JwtAuth::<User, _>::new(Arc::new(String::from("/login")));
 //! JwtAuth::<User, _>::new(Arc::new(vec![String::from("/login")]));
+//!
+//! // 精确、通配并限定请求方法
+//! JwtAuth::<User, _>::new(Rules::parse(&["POST /login", "/public/**"])?);
+//!
+//! // 可选认证 没有令牌也放行, 有令牌时验证并写入 Extension
+//! JwtAuth::<User, _>::default().optional();
+//!
+//! // 默认从 Authorization: Bearer 提取 可以改为 cookie、查询参数、自定义请求头
+//! JwtAuth::<User, _>::default().sources(&[TokenSource::Cookie("token"), TokenSource::Bearer]);
+//!
+//! // 滑动续期 剩余有效期不足一天时在响应头 x-renewed-token 返回新令牌
+//! JwtAuth::<User, _>::default().renew(60 * 60 * 24, Renew::Header(HeaderName::from_static("x-renewed-token")));
 //! ```
 
 crate::re_export! {
+    mod authority;
     mod extractor;
     mod middleware;
+    mod refresh;
+    mod revoke;
+    mod secret;
+    mod source;
 }
 
+use anyhow::{anyhow, bail};
 use axum::{
-    http::HeaderMap,
-    response::{IntoResponse, Response},
+    async_trait,
+    http::{HeaderMap, Uri},
 };
-use axum_extra::headers::{authorization::Bearer, Authorization, HeaderMapExt};
 use chrono::Local;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::res;
+use crate::resp::{AppError, ErrorKind, Res};
 
-fn auth_token<T: JwtToken>(header: &HeaderMap) -> Result<T, Response> {
-    let auth = header
-        .typed_get::<Authorization<Bearer>>()
-        .ok_or(res!(401, "身份认证失败: 请求未携带有效token").into_response())?;
-    T::decode(auth.token()).map_err(|err| res!(401, "身份认证失败: {err}").into_response())
+async fn auth_token<T: JwtToken>(headers: &HeaderMap, uri: &Uri, sources: &[TokenSource]) -> Result<Claims<T>, Res> {
+    auth_optional(headers, uri, sources)
+        .await?
+        .ok_or_else(|| AppError::new(ErrorKind::Unauthorized, "身份认证失败: 请求未携带有效token").into())
+}
+
+/// 没有携带令牌时返回 None, 携带了但无效时仍然拒绝
+async fn auth_optional<T: JwtToken>(
+    headers: &HeaderMap,
+    uri: &Uri,
+    sources: &[TokenSource],
+) -> Result<Option<Claims<T>>, Res> {
+    let Some(token) = find_token(headers, uri, sources) else {
+        return Ok(None);
+    };
+    match verify(&token).await {
+        Ok(claims) => Ok(Some(claims)),
+        // 令牌过期等错误使用对应的错误码
+        Err(VerifyError::Invalid(err)) => match err.downcast::<jsonwebtoken::errors::Error>() {
+            Ok(err) => Err(err.into()),
+            Err(err) => Err(AppError::new(ErrorKind::Unauthorized, format!("身份认证失败: {err}")).into()),
+        },
+        // 无法确认是否作废 不能当作令牌无效
+        Err(VerifyError::Store(err)) => Err(AppError::from(ErrorKind::Unavailable).with_cause(err).into()),
+    }
+}
+
+/// 认证失败的原因
+#[derive(Debug)]
+enum VerifyError {
+    /// 令牌无效、类型错误或已作废
+    Invalid(anyhow::Error),
+    /// 作废存储不可用
+    Store(anyhow::Error),
+}
+
+/// 校验令牌类型并查询是否已作废
+async fn verify<T: JwtToken>(token: &str) -> Result<Claims<T>, VerifyError> {
+    let claims = T::decode_claims(token).map_err(VerifyError::Invalid)?;
+    if claims.typ != TokenType::Access {
+        return Err(VerifyError::Invalid(anyhow!("不能使用刷新令牌认证")));
+    }
+    let store = T::revoke_store();
+    let revoked = match &claims.fam {
+        Some(fam) => store.is_family_revoked(fam).await.map_err(VerifyError::Store)?,
+        None => false,
+    };
+    if revoked || store.is_revoked(&claims.jti).await.map_err(VerifyError::Store)? {
+        return Err(VerifyError::Invalid(anyhow!("令牌已作废")));
+    }
+    Ok(claims)
+}
+
+/// 令牌类型 刷新令牌不能用于身份认证
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    #[default]
+    Access,
+    Refresh,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims<T> {
-    exp: usize,
-    data: T,
+    pub exp: usize,
+    /// 签发时间
+    #[serde(default)]
+    pub iat: usize,
+    /// 生效时间
+    #[serde(default)]
+    pub nbf: usize,
+    /// 签发者
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// 受众
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(default)]
+    pub typ: TokenType,
+    /// 令牌唯一 id
+    #[serde(default)]
+    pub jti: String,
+    /// 所属刷新家族 只有令牌对才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fam: Option<String>,
+    pub data: T,
 }
 
-pub trait JwtToken: Serialize + for<'a> Deserialize<'a> + Clone {
+impl<T: JwtToken> Claims<T> {
+    fn new(data: T, typ: TokenType, duration: usize) -> Self {
+        let now = Local::now().timestamp() as usize;
+        Self {
+            exp: now + duration,
+            iat: now,
+            nbf: now,
+            iss: T::issuer().map(Into::into),
+            aud: T::audience().map(Into::into),
+            typ,
+            jti: Uuid::new_v4().to_string(),
+            fam: None,
+            data,
+        }
+    }
+
+    fn family(mut self, fam: &str) -> Self {
+        self.fam = Some(fam.to_string());
+        self
+    }
+
+    /// 按当前载荷重新签发 access_token 保留刷新家族 用于滑动续期
+    pub fn reissue(&self) -> anyhow::Result<String> {
+        let mut access = Claims::new(self.data.clone(), TokenType::Access, T::duration());
+        access.fam = self.fam.clone();
+        encode_claims(&access)
+    }
+
+    /// 作废当前令牌 通过 [`JwtToken::encode_pair`] 签发的令牌同时作废整个刷新家族,
+    /// 包括刷新令牌和 [`Claims::reissue`] 续期的令牌
+    pub async fn revoke(&self) -> anyhow::Result<()> {
+        if let Some(fam) = &self.fam {
+            T::refresh_store().revoke(fam).await?;
+            T::revoke_store().revoke_family(fam, family_exp::<T>()).await?;
+        }
+        T::revoke_store().revoke(&self.jti, self.exp).await
+    }
+}
+
+#[async_trait]
+pub trait JwtToken: Serialize + for<'a> Deserialize<'a> + Clone + Send + Sync {
     fn secret() -> &'static Secret;
 
     fn encode(self) -> anyhow::Result<String> {
-        let exp = Local::now().timestamp() as usize + Self::duration();
-        let claims = Claims { data: self, exp };
-        let secret = Self::secret();
-        Ok(jsonwebtoken::encode(&secret.header, &claims, &secret.encoding_key)?)
+        encode_claims(&Claims::new(self, TokenType::Access, Self::duration()))
     }
 
     fn decode(token: &str) -> anyhow::Result<Self> {
-        let secret = Self::secret();
-        let data = jsonwebtoken::decode::<Claims<Self>>(token, &secret.decoding_key, &secret.validation)?;
-        Ok(data.claims.data)
+        let claims = Self::decode_claims(token)?;
+        if claims.typ != TokenType::Access {
+            bail!("不能使用刷新令牌认证");
+        }
+        Ok(claims.data)
+    }
+
+    /// 校验签名、时间、签发者和受众 不区分令牌类型
+    fn decode_claims(token: &str) -> anyhow::Result<Claims<Self>> {
+        Self::secret().decode_with(token, |validation| {
+            validation.leeway = Self::leeway();
+            validation.validate_nbf = true;
+            let mut required = vec!["exp"];
+            if let Some(iss) = Self::issuer() {
+                validation.set_issuer(&[iss]);
+                required.push("iss");
+            }
+            match Self::audience() {
+                Some(aud) => {
+                    validation.set_audience(&[aud]);
+                    required.push("aud");
+                }
+                None => validation.validate_aud = false,
+            }
+            validation.set_required_spec_claims(&required);
+        })
+    }
+
+    /// 签发令牌并写入 cookie, cookie 有效时长与令牌一致
+    fn encode_cookie(self, options: &CookieOptions) -> anyhow::Result<SetCookie> {
+        let token = self.encode()?;
+        Ok(options.set(&token, Self::duration()))
+    }
+
+    /// 签发 access_token 和 refresh_token 并开启新的刷新家族
+    async fn encode_pair(self) -> anyhow::Result<TokenPair> {
+        let fam = Uuid::new_v4().to_string();
+        let refresh = Claims::new(self, TokenType::Refresh, Self::refresh_duration()).family(&fam);
+        let pair = encode_pair(&refresh)?;
+        Self::refresh_store().issue(&fam, &refresh.jti, refresh.exp).await?;
+        Ok(pair)
+    }
+
+    /// 使用刷新令牌换取新的令牌对 旧的刷新令牌随即失效
+    async fn refresh(token: &str) -> anyhow::Result<TokenPair> {
+        let claims = Self::decode_claims(token)?;
+        if claims.typ != TokenType::Refresh {
+            bail!("不是刷新令牌");
+        }
+        let Some(fam) = claims.fam else {
+            bail!("刷新令牌缺少家族")
+        };
+
+        let refresh = Claims::new(claims.data, TokenType::Refresh, Self::refresh_duration()).family(&fam);
+        match Self::refresh_store()
+            .rotate(&fam, &claims.jti, &refresh.jti, refresh.exp)
+            .await?
+        {
+            Rotation::Rotated => encode_pair(&refresh),
+            Rotation::Reused => {
+                // 刷新令牌可能已泄露 已签发的 access_token 一起作废
+                Self::revoke_store().revoke_family(&fam, family_exp::<Self>()).await?;
+                bail!("刷新令牌已被使用")
+            }
+            Rotation::Expired => bail!("刷新令牌已失效"),
+        }
     }
 
     /// 持续时间默认半个月
     fn duration() -> usize {
         60 * 60 * 24 * 15
     }
+
+    /// 刷新令牌持续时间默认一个月
+    fn refresh_duration() -> usize {
+        60 * 60 * 24 * 30
+    }
+
+    /// 提取令牌的位置 [`Jwt`] 和 [`JwtAuth`] 默认使用
+    fn sources() -> &'static [TokenSource] {
+        &[TokenSource::Bearer]
+    }
+
+    /// 签发者 签发时写入 iss, 验证时要求一致
+    fn issuer() -> Option<&'static str> {
+        None
+    }
+
+    /// 受众 签发时写入 aud, 验证时要求一致
+    ///
+    /// 多个服务共用秘钥时为每个服务设置不同的受众, 防止令牌被其他服务接受
+    fn audience() -> Option<&'static str> {
+        None
+    }
+
+    /// 验证 exp、nbf 时允许的时钟误差(秒)
+    fn leeway() -> u64 {
+        60
+    }
+
+    /// 刷新令牌轮换记录 多实例部署时需要使用共享的存储
+    fn refresh_store() -> &'static dyn RefreshStore {
+        &*REFRESH_STORE
+    }
+
+    /// 令牌作废记录
+    fn revoke_store() -> &'static dyn RevokeStore {
+        &*REVOKE_STORE
+    }
 }
 
-/// 秘钥
-#[derive(Clone)]
-pub struct Secret {
-    decoding_key: DecodingKey,
-    encoding_key: EncodingKey,
-    validation: Validation,
-    header: Header,
+/// 现在作废家族时 家族内已签发的令牌最晚在此之后过期
+fn family_exp<T: JwtToken>() -> usize {
+    Local::now().timestamp() as usize + T::duration().max(T::refresh_duration())
 }
 
-impl Secret {
-    pub fn new(secret: &str) -> Self {
-        Self {
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            validation: Validation::default(),
-            header: Header::default(),
+fn encode_claims<T: JwtToken>(claims: &Claims<T>) -> anyhow::Result<String> {
+    T::secret().encode(claims)
+}
+
+fn encode_pair<T: JwtToken>(refresh: &Claims<T>) -> anyhow::Result<TokenPair> {
+    Ok(TokenPair {
+        access_token: refresh.reissue()?,
+        refresh_token: encode_claims(refresh)?,
+        expires_in: T::duration(),
+    })
+}
+
+#[tokio::test]
+async fn revoke_family_t() {
+    use once_cell::sync::Lazy;
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct User;
+
+    impl JwtToken for User {
+        fn secret() -> &'static Secret {
+            static SECRET: Lazy<Secret> = Lazy::new(|| Secret::new("secret"));
+            &SECRET
         }
     }
+
+    let pair = User.encode_pair().await.unwrap();
+    let claims = verify::<User>(&pair.access_token).await.unwrap();
+    let renewed = verify::<User>(&claims.reissue().unwrap()).await.unwrap();
+    assert_ne!(claims.jti, renewed.jti);
+
+    // 退出登录后续期的令牌和刷新令牌一起失效
+    claims.revoke().await.unwrap();
+    assert!(verify::<User>(&renewed.reissue().unwrap()).await.is_err());
+    assert!(User::refresh(&pair.refresh_token).await.is_err());
+
+    // 重放旧的刷新令牌 轮换后签发的令牌一起失效
+    let pair = User.encode_pair().await.unwrap();
+    let rotated = User::refresh(&pair.refresh_token).await.unwrap();
+    assert!(verify::<User>(&rotated.access_token).await.is_ok());
+    assert!(User::refresh(&pair.refresh_token).await.is_err());
+    assert!(verify::<User>(&rotated.access_token).await.is_err());
+    assert!(User::refresh(&rotated.refresh_token).await.is_err());
+}
+
+#[test]
+fn decode_claims_t() {
+    use once_cell::sync::Lazy;
+
+    static SECRET: Lazy<Secret> = Lazy::new(|| Secret::new("secret"));
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Api;
+
+    impl JwtToken for Api {
+        fn secret() -> &'static Secret {
+            &SECRET
+        }
+        fn issuer() -> Option<&'static str> {
+            Some("auth")
+        }
+        fn audience() -> Option<&'static str> {
+            Some("api")
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Admin;
+
+    impl JwtToken for Admin {
+        fn secret() -> &'static Secret {
+            &SECRET
+        }
+        fn issuer() -> Option<&'static str> {
+            Some("auth")
+        }
+        fn audience() -> Option<&'static str> {
+            Some("admin")
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Other;
+
+    impl JwtToken for Other {
+        fn secret() -> &'static Secret {
+            &SECRET
+        }
+        fn issuer() -> Option<&'static str> {
+            Some("other")
+        }
+        fn audience() -> Option<&'static str> {
+            Some("api")
+        }
+    }
+
+    // 同一秘钥 受众或签发者不同时拒绝
+    let token = Api.encode().unwrap();
+    assert!(Api::decode_claims(&token).is_ok());
+    assert!(Admin::decode_claims(&token).is_err());
+    assert!(Other::decode_claims(&token).is_err());
+
+    // exp、nbf 在 leeway 内仍然有效
+    let now = Local::now().timestamp() as usize;
+    let token = |nbf: usize, exp: usize| {
+        let claims = serde_json::json!({ "iss": "auth", "aud": "api", "nbf": nbf, "exp": exp, "data": null });
+        SECRET.encode(&claims).unwrap()
+    };
+    assert!(Api::decode_claims(&token(now + 30, now + 600)).is_ok());
+    assert!(Api::decode_claims(&token(now + 300, now + 600)).is_err());
+    assert!(Api::decode_claims(&token(now - 600, now - 30)).is_ok());
+    assert!(Api::decode_claims(&token(now - 600, now - 300)).is_err());
 }
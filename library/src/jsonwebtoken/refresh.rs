@@ -0,0 +1,199 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use axum::async_trait;
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// 默认的刷新令牌存储 所有 [`crate::jsonwebtoken::JwtToken`] 共用
+pub static REFRESH_STORE: Lazy<MemoryRefreshStore> = Lazy::new(MemoryRefreshStore::default);
+
+/// 登录或刷新后返回给客户端的令牌对
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// access_token 有效时长(秒)
+    pub expires_in: usize,
+}
+
+/// 刷新令牌轮换的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// 已替换为新的刷新令牌
+    Rotated,
+    /// 旧的刷新令牌被再次使用 家族已作废
+    Reused,
+    /// 家族不存在或已过期
+    Expired,
+}
+
+/// 刷新令牌轮换记录
+///
+/// 每次登录生成一个家族(fam), 家族内只有最新签发的刷新令牌有效。
+/// 旧的刷新令牌再次被使用时视为泄露, 整个家族作废, 需要重新登录。
+#[async_trait]
+pub trait RefreshStore: Send + Sync {
+    /// 记录家族当前有效的刷新令牌 记录保留到 exp
+    async fn issue(&self, fam: &str, jti: &str, exp: usize) -> anyhow::Result<()>;
+    /// 用 new_jti 替换家族中的 jti, jti 不是当前有效令牌时作废整个家族
+    async fn rotate(&self, fam: &str, jti: &str, new_jti: &str, exp: usize) -> anyhow::Result<Rotation>;
+    /// 作废整个家族
+    async fn revoke(&self, fam: &str) -> anyhow::Result<()>;
+}
+
+/// 内存刷新令牌存储
+///
+/// 数据只保存在内存中, 服务重启后所有刷新令牌失效。
+/// 多实例部署时其他实例签发的刷新令牌会被拒绝, 需要使用共享的存储 如 `PgRefreshStore`
+#[derive(Debug, Default)]
+pub struct MemoryRefreshStore {
+    families: Mutex<HashMap<String, Family>>,
+}
+
+#[derive(Debug)]
+struct Family {
+    jti: String,
+    exp: usize,
+}
+
+#[async_trait]
+impl RefreshStore for MemoryRefreshStore {
+    async fn issue(&self, fam: &str, jti: &str, exp: usize) -> anyhow::Result<()> {
+        let now = Local::now().timestamp() as usize;
+        let mut families = self.families.lock().unwrap();
+        families.retain(|_, f| f.exp > now);
+        families.insert(fam.to_string(), Family { jti: jti.to_string(), exp });
+        Ok(())
+    }
+
+    async fn rotate(&self, fam: &str, jti: &str, new_jti: &str, exp: usize) -> anyhow::Result<Rotation> {
+        let now = Local::now().timestamp() as usize;
+        let mut families = self.families.lock().unwrap();
+        Ok(match families.get_mut(fam) {
+            Some(family) if family.exp <= now => Rotation::Expired,
+            Some(family) if family.jti == jti => {
+                family.jti = new_jti.to_string();
+                family.exp = exp;
+                Rotation::Rotated
+            }
+            Some(_) => {
+                families.remove(fam);
+                Rotation::Reused
+            }
+            None => Rotation::Expired,
+        })
+    }
+
+    async fn revoke(&self, fam: &str) -> anyhow::Result<()> {
+        self.families.lock().unwrap().remove(fam);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn rotate_t() {
+    let store = MemoryRefreshStore::default();
+    let exp = Local::now().timestamp() as usize + 60;
+    store.issue("fam", "a", exp).await.unwrap();
+
+    assert_eq!(store.rotate("fam", "a", "b", exp).await.unwrap(), Rotation::Rotated);
+    // 重放旧令牌 整个家族作废
+    assert_eq!(store.rotate("fam", "a", "c", exp).await.unwrap(), Rotation::Reused);
+    assert_eq!(store.rotate("fam", "b", "c", exp).await.unwrap(), Rotation::Expired);
+}
+
+#[cfg(feature = "database")]
+pub use postgres::PgRefreshStore;
+
+#[cfg(feature = "database")]
+mod postgres {
+    use axum::async_trait;
+    use chrono::Local;
+    use diesel::{
+        sql_query,
+        sql_types::{BigInt, Text},
+    };
+    use diesel_async::RunQueryDsl;
+
+    use crate::{
+        database::postgres::PgPool,
+        jsonwebtoken::{RefreshStore, Rotation},
+    };
+
+    /// Postgres 刷新令牌存储 多个服务实例共享
+    ///
+    /// 家族当前有效的刷新令牌保存在 `jwt_refresh` 表中, 每次签发时顺便清理已过期的记录
+    #[derive(Clone)]
+    pub struct PgRefreshStore {
+        pool: PgPool,
+    }
+
+    impl PgRefreshStore {
+        /// 表不存在时自动创建
+        pub async fn new(pool: PgPool) -> anyhow::Result<Self> {
+            let mut conn = pool.get().await?;
+            sql_query(
+                "CREATE TABLE IF NOT EXISTS jwt_refresh (fam TEXT PRIMARY KEY, jti TEXT NOT NULL, exp BIGINT NOT NULL)",
+            )
+            .execute(&mut conn)
+            .await?;
+            drop(conn);
+            Ok(Self { pool })
+        }
+    }
+
+    #[async_trait]
+    impl RefreshStore for PgRefreshStore {
+        async fn issue(&self, fam: &str, jti: &str, exp: usize) -> anyhow::Result<()> {
+            let mut conn = self.pool.get().await?;
+            sql_query("DELETE FROM jwt_refresh WHERE exp <= $1")
+                .bind::<BigInt, _>(Local::now().timestamp())
+                .execute(&mut conn)
+                .await?;
+            sql_query("INSERT INTO jwt_refresh (fam, jti, exp) VALUES ($1, $2, $3)")
+                .bind::<Text, _>(fam)
+                .bind::<Text, _>(jti)
+                .bind::<BigInt, _>(exp as i64)
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        }
+
+        async fn rotate(&self, fam: &str, jti: &str, new_jti: &str, exp: usize) -> anyhow::Result<Rotation> {
+            let mut conn = self.pool.get().await?;
+            let now = Local::now().timestamp();
+            // 条件更新保证并发刷新时只有一个成功
+            let rotated =
+                sql_query("UPDATE jwt_refresh SET jti = $3, exp = $4 WHERE fam = $1 AND jti = $2 AND exp > $5")
+                    .bind::<Text, _>(fam)
+                    .bind::<Text, _>(jti)
+                    .bind::<Text, _>(new_jti)
+                    .bind::<BigInt, _>(exp as i64)
+                    .bind::<BigInt, _>(now)
+                    .execute(&mut conn)
+                    .await?;
+            if rotated > 0 {
+                return Ok(Rotation::Rotated);
+            }
+            let reused = sql_query("DELETE FROM jwt_refresh WHERE fam = $1 AND exp > $2")
+                .bind::<Text, _>(fam)
+                .bind::<BigInt, _>(now)
+                .execute(&mut conn)
+                .await?;
+            Ok(match reused > 0 {
+                true => Rotation::Reused,
+                false => Rotation::Expired,
+            })
+        }
+
+        async fn revoke(&self, fam: &str) -> anyhow::Result<()> {
+            let mut conn = self.pool.get().await?;
+            sql_query("DELETE FROM jwt_refresh WHERE fam = $1")
+                .bind::<Text, _>(fam)
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        }
+    }
+}
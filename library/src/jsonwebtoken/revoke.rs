@@ -0,0 +1,144 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use axum::async_trait;
+use chrono::Local;
+use once_cell::sync::Lazy;
+
+/// 默认的令牌作废存储 所有 [`crate::jsonwebtoken::JwtToken`] 共用
+pub static REVOKE_STORE: Lazy<MemoryRevokeStore> = Lazy::new(MemoryRevokeStore::default);
+
+/// 令牌作废存储 [`crate::jsonwebtoken::JwtAuth`] 和提取器认证时会查询
+#[async_trait]
+pub trait RevokeStore: Send + Sync {
+    /// 作废 jti 对应的令牌 记录保留到令牌过期时间 exp
+    async fn revoke(&self, jti: &str, exp: usize) -> anyhow::Result<()>;
+    /// jti 对应的令牌是否已作废
+    async fn is_revoked(&self, jti: &str) -> anyhow::Result<bool>;
+
+    /// 作废整个刷新家族 续期签发的令牌 jti 不同但家族相同, 记录保留到 exp
+    ///
+    /// 默认以 `fam:` 前缀和 jti 存在一起
+    async fn revoke_family(&self, fam: &str, exp: usize) -> anyhow::Result<()> {
+        self.revoke(&format!("fam:{fam}"), exp).await
+    }
+
+    /// 刷新家族是否已作废
+    async fn is_family_revoked(&self, fam: &str) -> anyhow::Result<bool> {
+        self.is_revoked(&format!("fam:{fam}")).await
+    }
+}
+
+/// 内存作废存储 令牌过期后记录自动淘汰
+#[derive(Debug, Default)]
+pub struct MemoryRevokeStore {
+    revoked: Mutex<HashMap<String, usize>>,
+}
+
+#[async_trait]
+impl RevokeStore for MemoryRevokeStore {
+    async fn revoke(&self, jti: &str, exp: usize) -> anyhow::Result<()> {
+        let now = Local::now().timestamp() as usize;
+        let mut revoked = self.revoked.lock().unwrap();
+        revoked.retain(|_, exp| *exp > now);
+        revoked.insert(jti.to_string(), exp);
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> anyhow::Result<bool> {
+        let now = Local::now().timestamp() as usize;
+        let revoked = self.revoked.lock().unwrap();
+        Ok(revoked.get(jti).is_some_and(|exp| *exp > now))
+    }
+}
+
+#[tokio::test]
+async fn revoke_t() {
+    let store = MemoryRevokeStore::default();
+    let now = Local::now().timestamp() as usize;
+
+    store.revoke("a", now + 60).await.unwrap();
+    assert!(store.is_revoked("a").await.unwrap());
+    assert!(!store.is_revoked("b").await.unwrap());
+
+    store.revoke_family("f", now + 60).await.unwrap();
+    assert!(store.is_family_revoked("f").await.unwrap());
+    assert!(!store.is_revoked("f").await.unwrap());
+
+    // 令牌过期后不再视为作废 下次作废时淘汰
+    store.revoke("b", now - 1).await.unwrap();
+    assert!(!store.is_revoked("b").await.unwrap());
+    store.revoke("c", now + 60).await.unwrap();
+    let revoked = store.revoked.lock().unwrap();
+    assert!(!revoked.contains_key("b"));
+    assert_eq!(revoked.len(), 3);
+}
+
+#[cfg(feature = "database")]
+pub use postgres::PgRevokeStore;
+
+#[cfg(feature = "database")]
+mod postgres {
+    use axum::async_trait;
+    use chrono::Local;
+    use diesel::{
+        sql_query,
+        sql_types::{BigInt, Text},
+        QueryableByName,
+    };
+    use diesel_async::RunQueryDsl;
+
+    use crate::{database::postgres::PgPool, jsonwebtoken::RevokeStore};
+
+    /// Postgres 作废存储 多个服务实例共享
+    ///
+    /// 作废记录保存在 `jwt_revoked` 表中, 每次作废时顺便清理已过期的记录
+    #[derive(Clone)]
+    pub struct PgRevokeStore {
+        pool: PgPool,
+    }
+
+    #[derive(QueryableByName)]
+    struct Count {
+        #[diesel(sql_type = BigInt)]
+        count: i64,
+    }
+
+    impl PgRevokeStore {
+        /// 表不存在时自动创建
+        pub async fn new(pool: PgPool) -> anyhow::Result<Self> {
+            let mut conn = pool.get().await?;
+            sql_query("CREATE TABLE IF NOT EXISTS jwt_revoked (jti TEXT PRIMARY KEY, exp BIGINT NOT NULL)")
+                .execute(&mut conn)
+                .await?;
+            drop(conn);
+            Ok(Self { pool })
+        }
+    }
+
+    #[async_trait]
+    impl RevokeStore for PgRevokeStore {
+        async fn revoke(&self, jti: &str, exp: usize) -> anyhow::Result<()> {
+            let mut conn = self.pool.get().await?;
+            sql_query("DELETE FROM jwt_revoked WHERE exp <= $1")
+                .bind::<BigInt, _>(Local::now().timestamp())
+                .execute(&mut conn)
+                .await?;
+            sql_query("INSERT INTO jwt_revoked (jti, exp) VALUES ($1, $2) ON CONFLICT (jti) DO NOTHING")
+                .bind::<Text, _>(jti)
+                .bind::<BigInt, _>(exp as i64)
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        }
+
+        async fn is_revoked(&self, jti: &str) -> anyhow::Result<bool> {
+            let mut conn = self.pool.get().await?;
+            let count: Count = sql_query("SELECT COUNT(*) AS count FROM jwt_revoked WHERE jti = $1 AND exp > $2")
+                .bind::<Text, _>(jti)
+                .bind::<BigInt, _>(Local::now().timestamp())
+                .get_result(&mut conn)
+                .await?;
+            Ok(count.count > 0)
+        }
+    }
+}
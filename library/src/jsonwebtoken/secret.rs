@@ -0,0 +1,427 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, PublicKeyUse, RSAKeyParameters,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use simple_asn1::ASN1Block;
+
+/// 秘钥集合
+///
+/// 使用 active 秘钥签发令牌并在 header 中写入 kid, 验证时根据 kid 选择秘钥,
+/// 轮换秘钥后旧秘钥留在集合中即可继续验证之前签发的令牌, 从集合中移除即退役。
+///
+/// 没有 kid 的令牌使用 active 秘钥验证
+///
+/// ```rust,ignore
+/// let secret = Secret::new("秘钥");
+/// let secret = Secret::from_pem_file(Algorithm::RS256, Some("private.pem"), "public.pem")?;
+///
+/// let secret = Secret::keyset(
+///     vec![
+///         JwtKey::from_pem_file(Algorithm::RS256, Some("2024.pem"), "2024.pub.pem")?.kid("2024"),
+///         // 旧秘钥只需要公钥
+///         JwtKey::from_pem_file(Algorithm::RS256, None, "2023.pub.pem")?.kid("2023"),
+///     ],
+///     "2024",
+/// )?;
+/// ```
+#[derive(Clone)]
+pub struct Secret {
+    keys: Vec<JwtKey>,
+    active: usize,
+}
+
+impl Secret {
+    /// HS256 秘钥
+    pub fn new(secret: &str) -> Self {
+        Self::from(JwtKey::hmac(Algorithm::HS256, secret.as_bytes()).unwrap())
+    }
+
+    /// 见 [`JwtKey::hmac`]
+    pub fn hmac(alg: Algorithm, secret: &[u8]) -> anyhow::Result<Self> {
+        JwtKey::hmac(alg, secret).map(Self::from)
+    }
+
+    /// 见 [`JwtKey::from_pem`]
+    pub fn from_pem(alg: Algorithm, private: Option<&[u8]>, public: &[u8]) -> anyhow::Result<Self> {
+        JwtKey::from_pem(alg, private, public).map(Self::from)
+    }
+
+    /// 见 [`JwtKey::from_der`]
+    pub fn from_der(alg: Algorithm, private: Option<&[u8]>, public: &[u8]) -> anyhow::Result<Self> {
+        JwtKey::from_der(alg, private, public).map(Self::from)
+    }
+
+    /// 见 [`JwtKey::from_pem_file`]
+    pub fn from_pem_file<P: AsRef<Path>>(alg: Algorithm, private: Option<P>, public: P) -> anyhow::Result<Self> {
+        JwtKey::from_pem_file(alg, private, public).map(Self::from)
+    }
+
+    /// 见 [`JwtKey::from_der_file`]
+    pub fn from_der_file<P: AsRef<Path>>(alg: Algorithm, private: Option<P>, public: P) -> anyhow::Result<Self> {
+        JwtKey::from_der_file(alg, private, public).map(Self::from)
+    }
+
+    /// 多个秘钥 每个秘钥都必须有不重复的 kid, active 为签发令牌使用的 kid
+    pub fn keyset(keys: Vec<JwtKey>, active: &str) -> anyhow::Result<Self> {
+        for (i, key) in keys.iter().enumerate() {
+            let Some(kid) = &key.kid else {
+                bail!("秘钥集合中的秘钥必须设置 kid")
+            };
+            if keys[..i].iter().any(|k| k.kid.as_ref() == Some(kid)) {
+                bail!("kid 重复: {kid}");
+            }
+        }
+
+        let active = keys
+            .iter()
+            .position(|k| k.kid.as_deref() == Some(active))
+            .ok_or_else(|| anyhow!("找不到 active 秘钥: {active}"))?;
+        if !keys[active].can_encode() {
+            bail!("active 秘钥只有公钥, 无法签发令牌");
+        }
+        Ok(Self { keys, active })
+    }
+
+    /// 签发令牌使用的秘钥
+    pub fn active(&self) -> &JwtKey {
+        &self.keys[self.active]
+    }
+
+    /// 签名算法
+    pub fn algorithm(&self) -> Algorithm {
+        self.active().alg
+    }
+
+    /// 是否可以签发令牌
+    pub fn can_encode(&self) -> bool {
+        self.active().can_encode()
+    }
+
+    /// 所有非对称秘钥的公钥 用于 /.well-known/jwks.json
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().filter_map(JwtKey::jwk).collect() }
+    }
+
+    /// 使用 active 秘钥签发
+    pub fn encode<C: Serialize>(&self, claims: &C) -> anyhow::Result<String> {
+        let key = self.active();
+        let Some(encoding_key) = &key.encoding_key else {
+            bail!("秘钥只有公钥, 无法签发令牌")
+        };
+        let mut header = Header::new(key.alg);
+        header.kid = key.kid.clone();
+        Ok(jsonwebtoken::encode(&header, claims, encoding_key)?)
+    }
+
+    /// 根据 header 中的 kid 选择秘钥验证
+    pub fn decode<C: DeserializeOwned>(&self, token: &str) -> anyhow::Result<C> {
+        self.decode_with(token, |_| {})
+    }
+
+    /// 同 [`Secret::decode`] 可以修改验证规则 算法由秘钥决定
+    pub fn decode_with<C: DeserializeOwned>(
+        &self,
+        token: &str,
+        rules: impl FnOnce(&mut Validation),
+    ) -> anyhow::Result<C> {
+        let key = match jsonwebtoken::decode_header(token)?.kid {
+            Some(kid) => self
+                .keys
+                .iter()
+                .find(|k| k.kid.as_ref() == Some(&kid))
+                .ok_or_else(|| anyhow!("未知的 kid: {kid}"))?,
+            None => self.active(),
+        };
+        let mut validation = key.validation.clone();
+        rules(&mut validation);
+        Ok(jsonwebtoken::decode::<C>(token, &key.decoding_key, &validation)?.claims)
+    }
+}
+
+impl From<JwtKey> for Secret {
+    fn from(key: JwtKey) -> Self {
+        Self { keys: vec![key], active: 0 }
+    }
+}
+
+/// 单个秘钥
+///
+/// 对称算法(HS*) 使用同一个秘钥签发和验证
+///
+/// 非对称算法(RS*, PS*, ES*, EdDSA) 私钥签发公钥验证, 只提供公钥时只能验证令牌
+#[derive(Clone)]
+pub struct JwtKey {
+    kid: Option<String>,
+    alg: Algorithm,
+    decoding_key: DecodingKey,
+    encoding_key: Option<EncodingKey>,
+    validation: Validation,
+    /// 非对称秘钥的公钥参数
+    public: Option<AlgorithmParameters>,
+}
+
+impl JwtKey {
+    /// HS256、HS384、HS512
+    pub fn hmac(alg: Algorithm, secret: &[u8]) -> anyhow::Result<Self> {
+        if family(alg) != Family::Hmac {
+            bail!("{alg:?} 不是对称算法");
+        }
+        let encoding_key = EncodingKey::from_secret(secret);
+        Ok(Self::build(
+            alg,
+            DecodingKey::from_secret(secret),
+            Some(encoding_key),
+            None,
+        ))
+    }
+
+    /// PEM 格式秘钥 private 为 None 时只能验证
+    pub fn from_pem(alg: Algorithm, private: Option<&[u8]>, public: &[u8]) -> anyhow::Result<Self> {
+        let (decoding_key, encoding_key) = match family(alg) {
+            Family::Hmac => bail!("{alg:?} 是对称算法, 请使用 hmac"),
+            Family::Rsa => (
+                DecodingKey::from_rsa_pem(public)?,
+                private.map(EncodingKey::from_rsa_pem).transpose()?,
+            ),
+            Family::Ec => (
+                DecodingKey::from_ec_pem(public)?,
+                private.map(EncodingKey::from_ec_pem).transpose()?,
+            ),
+            Family::Ed => (
+                DecodingKey::from_ed_pem(public)?,
+                private.map(EncodingKey::from_ed_pem).transpose()?,
+            ),
+        };
+        let params = public_params(alg, &pem_public_key(public)?)?;
+        Ok(Self::build(alg, decoding_key, encoding_key, Some(params)))
+    }
+
+    /// DER 格式秘钥 private 为 None 时只能验证
+    ///
+    /// 公钥格式与 [`DecodingKey::from_rsa_der`] 等一致
+    pub fn from_der(alg: Algorithm, private: Option<&[u8]>, public: &[u8]) -> anyhow::Result<Self> {
+        let (decoding_key, encoding_key) = match family(alg) {
+            Family::Hmac => bail!("{alg:?} 是对称算法, 请使用 hmac"),
+            Family::Rsa => (
+                DecodingKey::from_rsa_der(public),
+                private.map(EncodingKey::from_rsa_der),
+            ),
+            Family::Ec => (DecodingKey::from_ec_der(public), private.map(EncodingKey::from_ec_der)),
+            Family::Ed => (DecodingKey::from_ed_der(public), private.map(EncodingKey::from_ed_der)),
+        };
+        let params = public_params(alg, public)?;
+        Ok(Self::build(alg, decoding_key, encoding_key, Some(params)))
+    }
+
+    /// 从文件读取 PEM 格式秘钥
+    pub fn from_pem_file<P: AsRef<Path>>(alg: Algorithm, private: Option<P>, public: P) -> anyhow::Result<Self> {
+        let (private, public) = read_keys(private, public)?;
+        Self::from_pem(alg, private.as_deref(), &public)
+    }
+
+    /// 从文件读取 DER 格式秘钥
+    pub fn from_der_file<P: AsRef<Path>>(alg: Algorithm, private: Option<P>, public: P) -> anyhow::Result<Self> {
+        let (private, public) = read_keys(private, public)?;
+        Self::from_der(alg, private.as_deref(), &public)
+    }
+
+    /// 设置 kid
+    pub fn kid(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    /// 签名算法
+    pub fn algorithm(&self) -> Algorithm {
+        self.alg
+    }
+
+    /// 是否可以签发令牌
+    pub fn can_encode(&self) -> bool {
+        self.encoding_key.is_some()
+    }
+
+    /// 公钥 对称秘钥返回 None
+    pub fn jwk(&self) -> Option<Jwk> {
+        let common = CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: KeyAlgorithm::from_str(&format!("{:?}", self.alg)).ok(),
+            key_id: self.kid.clone(),
+            ..Default::default()
+        };
+        Some(Jwk { common, algorithm: self.public.clone()? })
+    }
+
+    fn build(
+        alg: Algorithm,
+        decoding_key: DecodingKey,
+        encoding_key: Option<EncodingKey>,
+        public: Option<AlgorithmParameters>,
+    ) -> Self {
+        Self {
+            kid: None,
+            alg,
+            decoding_key,
+            encoding_key,
+            validation: Validation::new(alg),
+            public,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Family {
+    Hmac,
+    Rsa,
+    Ec,
+    Ed,
+}
+
+fn family(alg: Algorithm) -> Family {
+    use Algorithm::*;
+    match alg {
+        HS256 | HS384 | HS512 => Family::Hmac,
+        RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => Family::Rsa,
+        ES256 | ES384 => Family::Ec,
+        EdDSA => Family::Ed,
+    }
+}
+
+fn read_keys<P: AsRef<Path>>(private: Option<P>, public: P) -> anyhow::Result<(Option<Vec<u8>>, Vec<u8>)> {
+    let read = |path: &Path| fs::read(path).map_err(|err| anyhow!("读取秘钥 {} 失败: {err}", path.display()));
+    let private = private.map(|p| read(p.as_ref())).transpose()?;
+    Ok((private, read(public.as_ref())?))
+}
+
+/// 取出 PEM 中的公钥 格式与 from_der 的公钥一致
+fn pem_public_key(public: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let pem = pem::parse(public)?;
+    match pem.tag() {
+        "RSA PUBLIC KEY" => Ok(pem.into_contents()),
+        // SubjectPublicKeyInfo: SEQUENCE { AlgorithmIdentifier, BIT STRING }
+        "PUBLIC KEY" => match simple_asn1::from_der(pem.contents())?.as_slice() {
+            [ASN1Block::Sequence(_, blocks)] => match blocks.as_slice() {
+                [_, ASN1Block::BitString(_, _, key)] => Ok(key.clone()),
+                _ => bail!("无法解析公钥"),
+            },
+            _ => bail!("无法解析公钥"),
+        },
+        tag => bail!("不支持的公钥格式: {tag}"),
+    }
+}
+
+/// 根据公钥生成 JWK 参数
+fn public_params(alg: Algorithm, public: &[u8]) -> anyhow::Result<AlgorithmParameters> {
+    let b64 = |bytes: &[u8]| URL_SAFE_NO_PAD.encode(bytes);
+    let params = match family(alg) {
+        Family::Hmac => bail!("对称秘钥没有公钥"),
+        // RSAPublicKey: SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+        Family::Rsa => match simple_asn1::from_der(public)?.as_slice() {
+            [ASN1Block::Sequence(_, blocks)] => match blocks.as_slice() {
+                [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => AlgorithmParameters::RSA(RSAKeyParameters {
+                    n: b64(&n.to_bytes_be().1),
+                    e: b64(&e.to_bytes_be().1),
+                    ..Default::default()
+                }),
+                _ => bail!("无法解析 RSA 公钥"),
+            },
+            _ => bail!("无法解析 RSA 公钥"),
+        },
+        // 未压缩的椭圆曲线点: 0x04 || x || y
+        Family::Ec => {
+            let (curve, size) = match alg {
+                Algorithm::ES384 => (EllipticCurve::P384, 48),
+                _ => (EllipticCurve::P256, 32),
+            };
+            if public.len() != 1 + size * 2 || public[0] != 4 {
+                bail!("无法解析 EC 公钥");
+            }
+            AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                curve,
+                x: b64(&public[1..=size]),
+                y: b64(&public[1 + size..]),
+                ..Default::default()
+            })
+        }
+        Family::Ed => AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            curve: EllipticCurve::Ed25519,
+            x: b64(public),
+            ..Default::default()
+        }),
+    };
+    Ok(params)
+}
+
+#[test]
+fn rotate_t() {
+    let old = JwtKey::hmac(Algorithm::HS256, b"old").unwrap().kid("old");
+    let new = JwtKey::hmac(Algorithm::HS256, b"new").unwrap().kid("new");
+    let claims = serde_json::json!({ "exp": usize::MAX });
+
+    let before = Secret::keyset(vec![old.clone()], "old").unwrap();
+    let token = before.encode(&claims).unwrap();
+
+    // 轮换后旧令牌仍然有效
+    let after = Secret::keyset(vec![new.clone(), old], "new").unwrap();
+    assert!(after.decode::<serde_json::Value>(&token).is_ok());
+
+    // 退役后旧令牌失效
+    let retired = Secret::keyset(vec![new], "new").unwrap();
+    assert!(retired.decode::<serde_json::Value>(&token).is_err());
+}
+
+#[test]
+fn asymmetric_t() {
+    macro_rules! key {
+        ($name:literal) => {
+            include_bytes!(concat!("testdata/", $name)) as &[u8]
+        };
+    }
+
+    let claims = serde_json::json!({ "exp": usize::MAX });
+    let keys: [(Algorithm, &[u8], &[u8]); 5] = [
+        (Algorithm::RS256, key!("rsa.pem"), key!("rsa.pub.pem")),
+        (Algorithm::PS256, key!("rsa.pem"), key!("rsa.pkcs1.pub.pem")),
+        (Algorithm::ES256, key!("ec256.pem"), key!("ec256.pub.pem")),
+        (Algorithm::ES384, key!("ec384.pem"), key!("ec384.pub.pem")),
+        (Algorithm::EdDSA, key!("ed25519.pem"), key!("ed25519.pub.pem")),
+    ];
+    for (alg, private, public) in keys {
+        let secret = Secret::from(JwtKey::from_pem(alg, Some(private), public).unwrap().kid("k"));
+        let token = secret.encode(&claims).unwrap();
+
+        // 只有公钥时可以验证
+        let verifier = Secret::from(JwtKey::from_pem(alg, None, public).unwrap().kid("k"));
+        assert!(verifier.decode::<serde_json::Value>(&token).is_ok());
+
+        // 通过 JWKS 中的公钥验证
+        let jwks = secret.jwks();
+        let jwk = jwks.find("k").unwrap();
+        let decoding_key = DecodingKey::from_jwk(jwk).unwrap();
+        assert!(jsonwebtoken::decode::<serde_json::Value>(&token, &decoding_key, &Validation::new(alg)).is_ok());
+    }
+
+    // 曲线与算法不一致
+    let p256 = key!("ec256.pub.pem");
+    assert!(JwtKey::from_pem(Algorithm::ES384, None, p256).is_err());
+
+    // DER 使用 PKCS#1 格式
+    let private = pem::parse(key!("rsa.pem")).unwrap();
+    let public = pem::parse(key!("rsa.pkcs1.pub.pem")).unwrap();
+    let secret = Secret::from_der(Algorithm::RS256, Some(private.contents()), public.contents()).unwrap();
+    let token = secret.encode(&claims).unwrap();
+    let pem = Secret::from_pem(Algorithm::RS256, None, key!("rsa.pub.pem")).unwrap();
+    assert!(pem.decode::<serde_json::Value>(&token).is_ok());
+    assert_eq!(
+        secret.active().jwk().unwrap().algorithm,
+        pem.active().jwk().unwrap().algorithm
+    );
+}
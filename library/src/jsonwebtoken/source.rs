@@ -0,0 +1,163 @@
+use std::{convert::Infallible, fmt::Write};
+
+use axum::{
+    http::{header::SET_COOKIE, HeaderMap, HeaderValue, Uri},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use axum_extra::headers::{authorization::Bearer, Authorization, Cookie, HeaderMapExt};
+
+/// 令牌来源 按顺序使用第一个找到的令牌
+///
+/// ```rust,ignore
+/// // 浏览器使用 HttpOnly cookie, 其他客户端使用 Authorization
+/// JwtAuth::<User, _>::default().sources(&[TokenSource::Cookie("token"), TokenSource::Bearer]);
+/// // WebSocket 握手、下载链接 ?token=xxx
+/// JwtAuth::<User, _>::default().sources(&[TokenSource::Query("token")]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    /// Authorization: Bearer xxx
+    Bearer,
+    /// 自定义请求头 值就是令牌
+    Header(&'static str),
+    /// cookie 名字
+    Cookie(&'static str),
+    /// 查询参数名字
+    Query(&'static str),
+}
+
+impl TokenSource {
+    pub fn find(&self, headers: &HeaderMap, uri: &Uri) -> Option<String> {
+        match *self {
+            TokenSource::Bearer => headers
+                .typed_get::<Authorization<Bearer>>()
+                .map(|auth| auth.token().to_string()),
+            TokenSource::Header(name) => headers.get(name)?.to_str().ok().map(Into::into),
+            TokenSource::Cookie(name) => headers.typed_get::<Cookie>()?.get(name).map(Into::into),
+            TokenSource::Query(name) => serde_urlencoded::from_str::<Vec<(String, String)>>(uri.query()?)
+                .ok()?
+                .into_iter()
+                .find_map(|(k, v)| (k == name).then_some(v)),
+        }
+    }
+}
+
+/// 按顺序查找令牌 空字符串视为没有
+pub(crate) fn find_token(headers: &HeaderMap, uri: &Uri, sources: &[TokenSource]) -> Option<String> {
+    sources
+        .iter()
+        .filter_map(|source| source.find(headers, uri))
+        .find(|token| !token.is_empty())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// 令牌 cookie 设置
+#[derive(Debug, Clone)]
+pub struct CookieOptions {
+    pub name: &'static str,
+    pub path: &'static str,
+    pub domain: Option<&'static str>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: SameSite,
+}
+
+impl Default for CookieOptions {
+    fn default() -> Self {
+        Self {
+            name: "token",
+            path: "/",
+            domain: None,
+            secure: true,
+            http_only: true,
+            same_site: SameSite::Lax,
+        }
+    }
+}
+
+impl CookieOptions {
+    /// 写入令牌 max_age 为 cookie 有效时长(秒)
+    pub fn set(&self, token: &str, max_age: usize) -> SetCookie {
+        let mut cookie = format!("{}={token}; Max-Age={max_age}", self.name);
+        self.attributes(&mut cookie);
+        SetCookie(HeaderValue::from_str(&cookie).expect("cookie 包含非法字符"))
+    }
+
+    /// 删除令牌 用于退出登录
+    pub fn clear(&self) -> SetCookie {
+        let mut cookie = format!("{}=; Max-Age=0", self.name);
+        self.attributes(&mut cookie);
+        SetCookie(HeaderValue::from_str(&cookie).expect("cookie 包含非法字符"))
+    }
+
+    fn attributes(&self, cookie: &mut String) {
+        write!(cookie, "; Path={}", self.path).unwrap();
+        if let Some(domain) = self.domain {
+            write!(cookie, "; Domain={domain}").unwrap();
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        let same_site = match self.same_site {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        };
+        write!(cookie, "; SameSite={same_site}").unwrap();
+    }
+}
+
+/// Set-Cookie 响应头 可以和 [`crate::resp::Res`] 组成元组返回
+///
+/// ```rust,ignore
+/// async fn login(VJson(user): VJson<User>) -> Result<(SetCookie, Res<()>), Res<()>> {
+///     let cookie = user.encode_cookie(&CookieOptions::default())?;
+///     Ok((cookie, res!(201, "登录成功")))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SetCookie(pub HeaderValue);
+
+impl IntoResponseParts for SetCookie {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        res.headers_mut().append(SET_COOKIE, self.0);
+        Ok(res)
+    }
+}
+
+impl IntoResponse for SetCookie {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+#[test]
+fn find_token_t() {
+    let mut headers = HeaderMap::new();
+    headers.insert("cookie", HeaderValue::from_static("a=1; token=cookie"));
+    headers.insert("authorization", HeaderValue::from_static("Bearer bearer"));
+    let uri: Uri = "/ws?x=1&token=query".parse().unwrap();
+
+    let find = |sources: &[TokenSource]| find_token(&headers, &uri, sources);
+    assert_eq!(find(&[TokenSource::Bearer]).as_deref(), Some("bearer"));
+    assert_eq!(
+        find(&[TokenSource::Cookie("token"), TokenSource::Bearer]).as_deref(),
+        Some("cookie")
+    );
+    assert_eq!(
+        find(&[TokenSource::Header("x-token"), TokenSource::Query("token")]).as_deref(),
+        Some("query")
+    );
+    assert_eq!(find(&[TokenSource::Cookie("none")]), None);
+}
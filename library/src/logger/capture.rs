@@ -0,0 +1,105 @@
+use axum::{
+    body::{self, Body, Bytes, HttpBody},
+    http::{header::CONTENT_TYPE, HeaderMap},
+};
+use serde_json::Value;
+
+use crate::logger::LoggerConfig;
+
+/// 读取不超过 capture_limit 的请求体或响应体 再放回去
+///
+/// 大小未知(流式)或超过限制时不读取, 不影响大文件下载
+pub(crate) async fn capture(body: Body, headers: &HeaderMap, config: &LoggerConfig) -> (Body, String) {
+    let size = body.size_hint();
+    let limit = config.capture_limit;
+    match size.upper() {
+        Some(0) => return (body, String::new()),
+        Some(upper) if upper <= limit as u64 => {}
+        Some(_) => return (body, format!("<{} bytes 未记录>", size.lower())),
+        None => return (body, "<流式 未记录>".into()),
+    }
+
+    match body::to_bytes(body, limit).await {
+        Ok(bytes) => {
+            let text = redact(&bytes, headers, &config.redact);
+            (Body::from(bytes), text)
+        }
+        Err(err) => (Body::empty(), format!("<读取失败 {err}>")),
+    }
+}
+
+/// JSON 和表单中的敏感字段替换为 ***
+fn redact(bytes: &Bytes, headers: &HeaderMap, fields: &[String]) -> String {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let sensitive = |key: &str| fields.iter().any(|f| f.eq_ignore_ascii_case(key));
+
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        if let Ok(pairs) = serde_urlencoded::from_bytes::<Vec<(String, String)>>(bytes) {
+            let pairs = pairs
+                .into_iter()
+                .map(|(k, v)| match sensitive(&k) {
+                    true => (k, "***".into()),
+                    false => (k, v),
+                })
+                .collect::<Vec<_>>();
+            return serde_urlencoded::to_string(pairs).unwrap_or_default();
+        }
+    }
+    if let Ok(mut value) = serde_json::from_slice::<Value>(bytes) {
+        redact_json(&mut value, &sensitive);
+        return value.to_string();
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => format!("<二进制 {} bytes>", bytes.len()),
+    }
+}
+
+/// 查询参数中的敏感字段替换为 *** 其余解码为 utf-8
+pub(crate) fn redact_query(query: &str, fields: &[String]) -> String {
+    let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query).unwrap_or_default();
+    pairs
+        .into_iter()
+        .map(|(k, v)| match fields.iter().any(|f| f.eq_ignore_ascii_case(&k)) {
+            true => format!("{k}=***"),
+            false => format!("{k}={v}"),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn redact_json(value: &mut Value, sensitive: &impl Fn(&str) -> bool) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match sensitive(key) {
+                    true => *value = Value::String("***".into()),
+                    false => redact_json(value, sensitive),
+                }
+            }
+        }
+        Value::Array(list) => list.iter_mut().for_each(|value| redact_json(value, sensitive)),
+        _ => {}
+    }
+}
+
+#[test]
+fn redact_t() {
+    let fields = vec!["password".to_string(), "token".to_string()];
+    let mut headers = HeaderMap::new();
+
+    let json = Bytes::from(r#"{"name":"a","password":"123","list":[{"Token":"x"}]}"#);
+    assert_eq!(
+        redact(&json, &headers, &fields),
+        r#"{"list":[{"Token":"***"}],"name":"a","password":"***"}"#
+    );
+
+    headers.insert(CONTENT_TYPE, "application/x-www-form-urlencoded".parse().unwrap());
+    let form = Bytes::from("name=a&password=123");
+    assert_eq!(redact(&form, &headers, &fields), "name=a&password=***");
+
+    assert_eq!(redact_query("name=%E5%BC%A0&Token=abc", &fields), "name=张&Token=***");
+}
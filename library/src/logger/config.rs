@@ -1,9 +1,17 @@
-use std::{fs, fs::File, io, io::Write, path::PathBuf};
+use std::{fs, fs::File, io, io::Write, path::PathBuf, thread, time::Duration};
 
-use chrono::{DateTime, Local};
+use chrono::{
+    format::{self, Parsed, StrftimeItems},
+    DateTime, Local, NaiveDate,
+};
 use color_string::{cs, Colored, Font::*};
 use serde::Deserialize;
 
+use crate::{
+    compare::Rules,
+    logger::{logfmt_line, LogFormat, Overflow, StatusClass},
+};
+
 pub struct LogMsg {
     pub begin: DateTime<Local>,
     pub end: DateTime<Local>,
@@ -11,11 +19,38 @@ pub struct LogMsg {
     pub path: String,
     pub status: u16,
     pub ip: String,
+    /// 需要在外层使用 [`crate::middleware::RequestIdLayer`]
+    pub request_id: Option<String>,
+    /// 额外字段 追加在固定字段之后
+    pub extra: Vec<(String, String)>,
 }
 
 impl LogMsg {
-    pub fn write(&self, config: &LoggerConfig, mut writer: impl Write, is_file: bool) -> io::Result<()> {
+    pub fn write(&self, config: &LoggerConfig, writer: impl Write, is_file: bool) -> io::Result<()> {
+        let format = match is_file {
+            true => config.file_format(),
+            false => config.stdout_format(),
+        };
+        match format {
+            LogFormat::Text => self.write_text(config, writer, is_file),
+            LogFormat::Json => self.write_json(writer),
+            LogFormat::Logfmt => self.write_logfmt(writer),
+        }
+    }
+
+    fn write_text(&self, config: &LoggerConfig, mut writer: impl Write, is_file: bool) -> io::Result<()> {
         let duration = (self.end - self.begin).to_std().unwrap_or_default();
+        let request_id = match &self.request_id {
+            Some(id) => format!(" │ {id}"),
+            None => String::new(),
+        };
+        let extra = match self.extra.is_empty() {
+            true => String::new(),
+            false => format!(
+                " │ {}",
+                logfmt_line(self.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            ),
+        };
 
         if config.color && !is_file {
             let status = match self.status / 100 {
@@ -35,7 +70,7 @@ impl LogMsg {
 
             writeln!(
                 writer,
-                "[{}] {} │ {} │ {:>8.2?} │ {} │ {} {}",
+                "[{}] {} │ {} │ {:>8.2?} │ {} │ {} {}{}{}",
                 self.end.format(&config.time).color(127, 132, 142),
                 cs!(Bold, Yellow => config.logo),
                 cs!(status; " {} ",self.status),
@@ -43,11 +78,13 @@ impl LogMsg {
                 cs!(Yellow; "{:<15}", self.ip),
                 cs!(method; " {:<6} ",self.method),
                 self.path,
+                request_id.color(127, 132, 142),
+                extra.color(127, 132, 142),
             )
         } else {
             writeln!(
                 writer,
-                "[{}] {} │ {} │ {:>8.2?} │ {:>15} │ {:<6} {}",
+                "[{}] {} │ {} │ {:>8.2?} │ {:>15} │ {:<6} {}{}{}",
                 self.begin.format(&config.time),
                 config.logo,
                 self.status,
@@ -55,6 +92,8 @@ impl LogMsg {
                 self.ip,
                 self.method,
                 self.path,
+                request_id,
+                extra,
             )
         }
     }
@@ -70,29 +109,170 @@ pub struct LoggerConfig {
     pub color: bool,
     pub stdout: bool,
     pub delete: Option<i64>,
+    /// 输出格式 默认 text
+    #[serde(default)]
+    pub format: LogFormat,
+    /// 终端单独使用的格式 不配置时使用 format
+    #[serde(default)]
+    pub stdout_format: Option<LogFormat>,
+    /// 文件单独使用的格式 不配置时使用 format
+    #[serde(default)]
+    pub file_format: Option<LogFormat>,
+    /// 单个日志文件最大字节数 超过后改名为 name.1、name.2...
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    /// 每天最多保留的切分文件数量
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    /// 切分出的文件使用 gzip 压缩
+    #[serde(default)]
+    pub compress: bool,
+    /// tracing 日志级别 error warn info debug trace
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// 访问日志额外记录的字段
+    #[serde(default)]
+    pub fields: Vec<LogField>,
+    /// 从 [`crate::jsonwebtoken::JwtAuth`] 认证的载荷中记录的字段 如 name
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// 不记录的请求 如 "GET /health" "/static/**"
+    #[serde(default)]
+    pub exclude: Rules,
+    /// 只记录这些状态码类别 如 "2xx" "5xx" 为空时全部记录
+    #[serde(default)]
+    pub status: Vec<StatusClass>,
+    /// 成功请求的采样率 0.0 ~ 1.0, 4xx、5xx 和慢请求总是记录
+    #[serde(default = "default_sample")]
+    pub sample: f64,
+    /// 慢请求阈值(毫秒) 超过时总是记录
+    #[serde(default)]
+    pub slow: Option<u64>,
+    /// 日志队列长度
+    #[serde(default = "default_buffer")]
+    pub buffer: usize,
+    /// 队列满时丢弃(drop)还是等待(block)
+    #[serde(default)]
+    pub overflow: Overflow,
+    /// 缓冲刷新间隔(毫秒)
+    #[serde(default = "default_flush")]
+    pub flush: u64,
+    /// 记录请求体和响应体的请求 用于调试 如 "POST /user/**"
+    #[serde(default)]
+    pub capture: Rules,
+    /// 记录的最大字节数 超过或大小未知(流式)时不读取
+    #[serde(default = "default_capture_limit")]
+    pub capture_limit: usize,
+    /// 记录时隐藏的 JSON、表单字段和查询参数 不区分大小写
+    #[serde(default = "default_redact")]
+    pub redact: Vec<String>,
+}
+
+/// 访问日志可选字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogField {
+    UserAgent,
+    Referer,
+    Query,
+    /// HTTP 版本
+    Version,
+    /// 请求体字节数
+    ReqSize,
+    /// 响应体字节数
+    ResSize,
+}
+
+impl LogField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogField::UserAgent => "user_agent",
+            LogField::Referer => "referer",
+            LogField::Query => "query",
+            LogField::Version => "version",
+            LogField::ReqSize => "req_size",
+            LogField::ResSize => "res_size",
+        }
+    }
+}
+
+crate::gen_default! {
+    default_level, "info";
+}
+
+crate::gen_default! {
+    default_sample, 1.0, f64;
+    default_buffer, 8192, usize;
+    default_flush, 1000, u64;
+    default_capture_limit, 4096, usize;
+    default_redact, vec!["password".into(), "token".into(), "refresh_token".into(), "secret".into()], Vec<String>;
 }
 
 impl LoggerConfig {
+    pub fn stdout_format(&self) -> LogFormat {
+        self.stdout_format.unwrap_or(self.format)
+    }
+
+    pub fn file_format(&self) -> LogFormat {
+        self.file_format.unwrap_or(self.format)
+    }
+
+    /// 删除超过 delete 天的日志文件
+    ///
+    /// 只处理文件名符合 name 格式的文件(包括切分出的 name.N 和 name.N.gz),
+    /// 优先使用文件名中的日期, 文件名不含日期时使用修改时间
     pub fn delete_log_file(&self) -> anyhow::Result<()> {
-        if let Some(n) = self.delete {
-            let now = Local::now();
-            for entry in fs::read_dir(&self.path)?.flatten() {
-                let meta = entry.metadata()?;
-                let created_at: DateTime<Local> = meta.created()?.into();
-                if (now - created_at).num_days() >= n {
-                    fs::remove_file(entry.path())?
-                }
-            }
+        let Some(n) = self.delete else {
+            return Ok(());
         };
+        let today = Local::now().date_naive();
+        for entry in fs::read_dir(&self.path)?.flatten() {
+            let Some(date) = entry.file_name().to_str().and_then(|name| self.parse_log_name(name)) else {
+                continue;
+            };
+            let date = match date {
+                Some(date) => date,
+                None => DateTime::<Local>::from(entry.metadata()?.modified()?).date_naive(),
+            };
+            if (today - date).num_days() >= n {
+                fs::remove_file(entry.path())?
+            }
+        }
         Ok(())
     }
 
-    /// 更新日志文件 删除过期文件
-    pub fn update_log_file(&self, time: &DateTime<Local>) -> File {
-        if let Err(err) = self.delete_log_file() {
-            eprintln!("日志删除失败: {err}")
+    /// 不是日志文件返回 None, 文件名中没有日期返回 Some(None)
+    fn parse_log_name(&self, file_name: &str) -> Option<Option<NaiveDate>> {
+        let parse = |name: &str| {
+            let mut parsed = Parsed::new();
+            format::parse(&mut parsed, name, StrftimeItems::new(&self.name)).ok()?;
+            Some(parsed.to_naive_date().ok())
+        };
+        parse(file_name).or_else(|| {
+            // 切分出的 name.N 和 name.N.gz
+            let name = file_name.strip_suffix(".gz").unwrap_or(file_name);
+            let (name, index) = name.rsplit_once('.')?;
+            index.parse::<usize>().ok()?;
+            parse(name)
+        })
+    }
+
+    /// 定时清理过期日志 每小时一次
+    pub(crate) fn spawn_cleaner(&self) {
+        if self.delete.is_none() || !self.file {
+            return;
         }
+        let config = self.clone();
+        thread::spawn(move || loop {
+            if let Err(err) = config.delete_log_file() {
+                eprintln!("日志删除失败: {err}")
+            }
+            thread::sleep(Duration::from_secs(60 * 60));
+        });
+    }
 
+    /// 打开日志文件
+    pub fn update_log_file(&self, time: &DateTime<Local>) -> File {
         fs::create_dir_all(&self.path).expect("自动创建日志文件父级目录失败");
         let name = time.format(&self.name).to_string();
         File::options()
@@ -114,6 +294,40 @@ impl Default for LoggerConfig {
             color: true,
             stdout: true,
             delete: None,
+            format: LogFormat::Text,
+            stdout_format: None,
+            file_format: None,
+            max_size: None,
+            max_files: None,
+            compress: false,
+            level: default_level(),
+            fields: Vec::new(),
+            identity: None,
+            exclude: Rules::default(),
+            status: Vec::new(),
+            sample: 1.0,
+            slow: None,
+            buffer: default_buffer(),
+            overflow: Overflow::Drop,
+            flush: default_flush(),
+            capture: Rules::default(),
+            capture_limit: default_capture_limit(),
+            redact: default_redact(),
         }
     }
 }
+
+#[test]
+fn parse_log_name_t() {
+    let config = LoggerConfig { name: "%Y%m%d.log".into(), ..Default::default() };
+    let date = NaiveDate::from_ymd_opt(2024, 3, 1);
+    assert_eq!(config.parse_log_name("20240301.log"), Some(date));
+    assert_eq!(config.parse_log_name("20240301.log.2"), Some(date));
+    assert_eq!(config.parse_log_name("20240301.log.2.gz"), Some(date));
+    assert_eq!(config.parse_log_name("notes.txt"), None);
+    assert_eq!(config.parse_log_name("20240301.log.bak"), None);
+
+    let config = LoggerConfig { name: "app.log".into(), ..Default::default() };
+    assert_eq!(config.parse_log_name("app.log.1"), Some(None));
+    assert_eq!(config.parse_log_name("other.log"), None);
+}
@@ -0,0 +1,116 @@
+use std::{collections::BTreeMap, io, io::Write};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use color_string::{cs, Colored, Font::*};
+use serde::Serialize;
+use tracing::Level;
+
+use crate::logger::{logfmt_line, LogFormat, LogMsg, LoggerConfig};
+
+/// 通过 tracing 记录的应用日志
+pub struct EventMsg {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /// 事件字段和所在 span 的字段
+    pub fields: Vec<(String, String)>,
+}
+
+/// 写入线程接收的日志
+pub enum LogRecord {
+    /// 访问日志
+    Access(LogMsg),
+    /// 应用日志
+    Event(EventMsg),
+}
+
+impl LogRecord {
+    /// 用于按日期切换日志文件
+    pub fn time(&self) -> DateTime<Local> {
+        match self {
+            LogRecord::Access(msg) => msg.begin,
+            LogRecord::Event(msg) => msg.time,
+        }
+    }
+
+    pub fn write(&self, config: &LoggerConfig, writer: impl Write, is_file: bool) -> io::Result<()> {
+        match self {
+            LogRecord::Access(msg) => msg.write(config, writer, is_file),
+            LogRecord::Event(msg) => msg.write(config, writer, is_file),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    timestamp: String,
+    level: &'a str,
+    target: &'a str,
+    message: &'a str,
+    #[serde(flatten)]
+    fields: BTreeMap<&'a str, &'a str>,
+}
+
+impl EventMsg {
+    pub fn write(&self, config: &LoggerConfig, mut writer: impl Write, is_file: bool) -> io::Result<()> {
+        let format = match is_file {
+            true => config.file_format(),
+            false => config.stdout_format(),
+        };
+        let fields = self.fields.iter().map(|(k, v)| format!(" {k}={v}")).collect::<String>();
+
+        match format {
+            LogFormat::Text if config.color && !is_file => {
+                let level = match self.level {
+                    Level::ERROR => BgRed,
+                    Level::WARN => BgYellow,
+                    Level::INFO => BgGreen,
+                    Level::DEBUG => BgBlue,
+                    Level::TRACE => BgPurple,
+                };
+                writeln!(
+                    writer,
+                    "[{}] {} │ {} │ {} │ {}{}",
+                    self.time.format(&config.time).color(127, 132, 142),
+                    cs!(Bold, Yellow => config.logo),
+                    cs!(level; " {:<5} ", self.level),
+                    self.target.color(127, 132, 142),
+                    self.message,
+                    fields.color(127, 132, 142),
+                )
+            }
+            LogFormat::Text => writeln!(
+                writer,
+                "[{}] {} │ {:<5} │ {} │ {}{}",
+                self.time.format(&config.time),
+                config.logo,
+                self.level,
+                self.target,
+                self.message,
+                fields,
+            ),
+            LogFormat::Json => {
+                let line = JsonLine {
+                    timestamp: self.time.to_rfc3339_opts(SecondsFormat::Micros, false),
+                    level: self.level.as_str(),
+                    target: &self.target,
+                    message: &self.message,
+                    fields: self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect(),
+                };
+                serde_json::to_writer(&mut writer, &line)?;
+                writeln!(writer)
+            }
+            LogFormat::Logfmt => {
+                let mut pairs = vec![
+                    ("timestamp", self.time.to_rfc3339_opts(SecondsFormat::Micros, false)),
+                    ("level", self.level.to_string()),
+                    ("target", self.target.clone()),
+                    ("message", self.message.clone()),
+                ];
+                pairs.extend(self.fields.iter().map(|(k, v)| (k.as_str(), v.clone())));
+                writeln!(writer, "{}", logfmt_line(pairs.iter().map(|(k, v)| (*k, v.as_str()))))
+            }
+        }
+    }
+}
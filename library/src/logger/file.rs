@@ -0,0 +1,188 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+
+use chrono::{DateTime, Local};
+use flate2::{write::GzEncoder, Compression};
+
+use crate::logger::{LogRecord, LoggerConfig};
+
+/// 当前写入的日志文件 按日期和大小切换
+pub(crate) struct LogFile {
+    file: BufWriter<File>,
+    size: u64,
+    time: DateTime<Local>,
+    /// 后台压缩和清理切分文件的线程
+    cleanup: Option<JoinHandle<()>>,
+}
+
+impl LogFile {
+    pub fn open(config: &LoggerConfig, time: DateTime<Local>) -> Self {
+        let file = config.update_log_file(&time);
+        let size = file.metadata().map(|meta| meta.len()).unwrap_or_default();
+        Self { file: BufWriter::new(file), size, time, cleanup: None }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    /// 等待后台压缩和清理完成
+    pub fn join(&mut self) {
+        if let Some(Err(_)) = self.cleanup.take().map(JoinHandle::join) {
+            eprintln!("日志清理线程异常退出")
+        }
+    }
+
+    pub fn write(&mut self, config: &LoggerConfig, record: &LogRecord) -> io::Result<()> {
+        let time = record.time();
+        if self.time.date_naive() != time.date_naive() {
+            self.flush()?;
+            *self = Self { cleanup: self.cleanup.take(), ..Self::open(config, time) };
+        } else if config.max_size.is_some_and(|max| self.size >= max) {
+            self.rotate(config)?;
+        }
+
+        let mut line = Vec::new();
+        record.write(config, &mut line, true)?;
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// 当前文件改名为 name.N 序号递增 再打开新文件
+    ///
+    /// 压缩和清理在后台线程中依次执行 不阻塞写入
+    fn rotate(&mut self, config: &LoggerConfig) -> io::Result<()> {
+        let name = self.time.format(&config.name).to_string();
+        let current = config.path.join(&name);
+        let rotated = rotated_files(&config.path, &name)?;
+        self.flush()?;
+
+        let index = rotated.last().map_or(1, |(index, _)| index + 1);
+        let target = config.path.join(format!("{name}.{index}"));
+        fs::rename(&current, &target)?;
+        *self = Self {
+            cleanup: self.cleanup.take(),
+            ..Self::open(config, self.time)
+        };
+
+        if !config.compress && config.max_files.is_none() {
+            return Ok(());
+        }
+        // 等待上一次完成 先压缩再清理, 清理时不会删除正在压缩的文件
+        let previous = self.cleanup.take();
+        let (dir, compress_file, max_files) = (config.path.clone(), config.compress, config.max_files);
+        self.cleanup = Some(thread::spawn(move || {
+            if let Some(previous) = previous {
+                let _ = previous.join();
+            }
+            if compress_file {
+                if let Err(err) = compress(&target) {
+                    eprintln!("压缩日志失败 {} -> {err}", target.display())
+                }
+            }
+            if let Some(max) = max_files {
+                if let Err(err) = prune(&dir, &name, max) {
+                    eprintln!("清理日志失败 {name} -> {err}")
+                }
+            }
+        }));
+        Ok(())
+    }
+}
+
+/// 只保留最新的 max 个序号 文件可能已被按时间清理
+fn prune(dir: &Path, name: &str, max: usize) -> io::Result<()> {
+    let rotated = rotated_files(dir, name)?;
+    let mut indexes = rotated.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+    indexes.dedup();
+    let excess = &indexes[..indexes.len().saturating_sub(max)];
+    for (_, path) in rotated.iter().filter(|(index, _)| excess.contains(index)) {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// name.N 和 name.N.gz 按序号排序
+fn rotated_files(dir: &Path, name: &str) -> io::Result<Vec<(usize, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let file_name = entry.file_name();
+        let Some(suffix) = file_name.to_str().and_then(|f| f.strip_prefix(name)?.strip_prefix('.')) else {
+            continue;
+        };
+        let index = suffix.strip_suffix(".gz").unwrap_or(suffix);
+        if let Ok(index) = index.parse::<usize>() {
+            files.push((index, entry.path()));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// 压缩为 path.gz 并删除原文件 保留原文件的修改时间 方便按时间清理
+fn compress(path: &Path) -> io::Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+
+    let modified = fs::metadata(path)?.modified()?;
+    let gz = File::create(&gz_path)?;
+    let mut encoder = GzEncoder::new(gz, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.set_modified(modified)?;
+    fs::remove_file(path)
+}
+
+#[test]
+fn rotate_t() {
+    let dir = std::env::temp_dir().join(format!("logger-rotate-{}", std::process::id()));
+    let config = LoggerConfig {
+        path: dir.clone(),
+        name: "test.log".into(),
+        file: true,
+        max_size: Some(1),
+        max_files: Some(2),
+        ..Default::default()
+    };
+    let now = Local::now();
+    let msg = LogRecord::Access(crate::logger::LogMsg {
+        begin: now,
+        end: now,
+        method: "GET".into(),
+        path: "/".into(),
+        status: 200,
+        ip: "127.0.0.1".into(),
+        request_id: None,
+        extra: Vec::new(),
+    });
+
+    let mut file = LogFile::open(&config, now);
+    for _ in 0..4 {
+        file.write(&config, &msg).unwrap();
+    }
+    file.join();
+    let rotated = rotated_files(&dir, "test.log").unwrap();
+    let indexes = rotated.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+    assert_eq!(indexes, [2, 3]);
+
+    // 压缩后清理 不残留未压缩或超出数量的文件
+    let config = LoggerConfig { compress: true, ..config };
+    for _ in 0..3 {
+        file.write(&config, &msg).unwrap();
+    }
+    file.join();
+    let rotated = rotated_files(&dir, "test.log").unwrap();
+    let names = rotated
+        .iter()
+        .filter_map(|(_, path)| path.file_name()?.to_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["test.log.5.gz", "test.log.6.gz"]);
+    fs::remove_dir_all(dir).unwrap();
+}
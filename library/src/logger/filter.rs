@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter};
+
+use anyhow::bail;
+use axum::http::Method;
+use chrono::Duration;
+use serde::Deserialize;
+
+use crate::{compare::CompareReq, logger::LoggerConfig};
+
+/// 状态码类别 配置文件中写作 "2xx"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct StatusClass(pub u16);
+
+impl TryFrom<String> for StatusClass {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_bytes() {
+            [class @ b'1'..=b'5', b'x', b'x'] => Ok(Self(u16::from(class - b'0'))),
+            _ => bail!("状态码类别格式为 1xx ~ 5xx: {value}"),
+        }
+    }
+}
+
+impl Display for StatusClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}xx", self.0)
+    }
+}
+
+impl LoggerConfig {
+    /// 请求是否被 exclude 排除
+    pub fn excluded(&self, method: &Method, path: &str) -> bool {
+        self.exclude.compare_req(method, path)
+    }
+
+    /// 按状态码、耗时和采样率决定是否记录
+    pub fn should_log(&self, status: u16, latency: Duration) -> bool {
+        if self.slow.is_some_and(|slow| latency.num_milliseconds() >= slow as i64) {
+            return true;
+        }
+        if !self.status.is_empty() && !self.status.iter().any(|class| class.0 == status / 100) {
+            return false;
+        }
+        status >= 400 || self.sample >= 1.0 || rand::random::<f64>() < self.sample
+    }
+}
+
+#[test]
+fn should_log_t() {
+    let config = LoggerConfig {
+        exclude: crate::compare::Rules::parse(&["GET /health", "/static/**"]).unwrap(),
+        status: vec![
+            "2xx".to_string().try_into().unwrap(),
+            "5XX".to_string().try_into().unwrap(),
+        ],
+        sample: 0.0,
+        slow: Some(500),
+        ..Default::default()
+    };
+    assert!(config.excluded(&Method::GET, "/health"));
+    assert!(!config.excluded(&Method::POST, "/health"));
+    assert!(config.excluded(&Method::GET, "/static/a.css"));
+
+    let fast = Duration::microseconds(10_000);
+    assert!(!config.should_log(200, fast));
+    assert!(!config.should_log(404, fast));
+    assert!(config.should_log(500, fast));
+    assert!(config.should_log(200, Duration::microseconds(600_000)));
+    assert!(StatusClass::try_from("6xx".to_string()).is_err());
+}
@@ -0,0 +1,140 @@
+use std::{collections::BTreeMap, io, io::Write};
+
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+
+use crate::logger::LogMsg;
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `│` 分隔 便于阅读 终端可以染色
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象
+    Json,
+    /// 每行 key=value
+    Logfmt,
+}
+
+/// JSON 格式的一行 字段顺序固定 额外字段排在最后
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    timestamp: String,
+    method: &'a str,
+    path: &'a str,
+    status: u16,
+    latency_us: i64,
+    ip: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<&'a str>,
+    #[serde(flatten)]
+    extra: BTreeMap<&'a str, &'a str>,
+}
+
+impl LogMsg {
+    fn timestamp(&self) -> String {
+        self.begin.to_rfc3339_opts(SecondsFormat::Micros, false)
+    }
+
+    fn latency_us(&self) -> i64 {
+        (self.end - self.begin).num_microseconds().unwrap_or(i64::MAX)
+    }
+
+    pub(crate) fn write_json(&self, mut writer: impl Write) -> io::Result<()> {
+        let line = JsonLine {
+            timestamp: self.timestamp(),
+            method: &self.method,
+            path: &self.path,
+            status: self.status,
+            latency_us: self.latency_us(),
+            ip: &self.ip,
+            request_id: self.request_id.as_deref(),
+            extra: self.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect(),
+        };
+        serde_json::to_writer(&mut writer, &line)?;
+        writeln!(writer)
+    }
+
+    pub(crate) fn write_logfmt(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut pairs = vec![
+            ("timestamp", self.timestamp()),
+            ("method", self.method.clone()),
+            ("path", self.path.clone()),
+            ("status", self.status.to_string()),
+            ("latency_us", self.latency_us().to_string()),
+            ("ip", self.ip.clone()),
+        ];
+        if let Some(id) = &self.request_id {
+            pairs.push(("request_id", id.clone()));
+        }
+        pairs.extend(self.extra.iter().map(|(k, v)| (k.as_str(), v.clone())));
+        writeln!(writer, "{}", logfmt_line(pairs.iter().map(|(k, v)| (*k, v.as_str()))))
+    }
+}
+
+/// 拼接为 key=value key=value
+pub(crate) fn logfmt_line<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut line = String::new();
+    for (key, value) in pairs {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(key);
+        line.push('=');
+        logfmt_value(&mut line, value);
+    }
+    line
+}
+
+/// 包含空格、引号、等号或为空时加引号
+fn logfmt_value(line: &mut String, value: &str) {
+    let quote = value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=');
+    if !quote {
+        line.push_str(value);
+        return;
+    }
+    line.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+#[test]
+fn format_t() {
+    use chrono::{Duration, Local};
+
+    let begin = Local::now();
+    let msg = LogMsg {
+        begin,
+        end: begin + Duration::microseconds(1500),
+        method: "GET".into(),
+        path: "/a b".into(),
+        status: 200,
+        ip: "127.0.0.1".into(),
+        request_id: Some("id".into()),
+        extra: vec![("user".into(), "张三".into())],
+    };
+
+    let mut json = Vec::new();
+    msg.write_json(&mut json).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(value["latency_us"], 1500);
+    assert_eq!(value["path"], "/a b");
+    assert_eq!(value["request_id"], "id");
+    assert_eq!(value["user"], "张三");
+
+    let mut logfmt = Vec::new();
+    msg.write_logfmt(&mut logfmt).unwrap();
+    let logfmt = String::from_utf8(logfmt).unwrap();
+    assert!(
+        logfmt.contains(r#" method=GET path="/a b" status=200 latency_us=1500 ip=127.0.0.1 request_id=id user=张三"#)
+    );
+}
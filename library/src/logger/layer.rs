@@ -0,0 +1,133 @@
+use std::fmt;
+
+use chrono::Local;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Level, Metadata, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::logger::{writer::LogSender, EventMsg, LogRecord};
+
+/// tracing 日志写入 [`crate::logger::Logger`] 的输出 和访问日志使用同一个文件
+///
+/// ```rust,ignore
+/// let logger = Logger::new(CONFIG.logger.clone());
+/// tracing_subscriber::registry().with(logger.tracing_layer()).init();
+///
+/// // 处理请求时会附带 request span 中的 method、path、request_id
+/// tracing::info!(user = %name, "登录成功");
+/// ```
+pub struct TracingLayer {
+    pub(crate) sender: LogSender,
+    pub(crate) level: Level,
+}
+
+/// 保存在 span 中的字段
+struct SpanFields(Vec<(String, String)>);
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            name => self.fields.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{value:?}"),
+            name => self.fields.push((name.to_string(), format!("{value:?}"))),
+        }
+    }
+}
+
+impl<S> Layer<S> for TracingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    /// 级别只过滤事件 span 始终启用, 否则 level 高于 info 时事件会丢失 request span 的字段
+    fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+        metadata.is_span() || *metadata.level() <= self.level
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                fields.0.extend(visitor.fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        // 外层 span 的字段在前
+        let mut fields = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(span_fields) = span.extensions().get::<SpanFields>() {
+                    fields.extend(span_fields.0.iter().cloned());
+                }
+            }
+        }
+        fields.extend(visitor.fields);
+
+        let metadata = event.metadata();
+        let msg = EventMsg {
+            time: Local::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields,
+        };
+        self.sender.send(LogRecord::Event(msg));
+    }
+}
+
+#[test]
+fn span_level_t() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let dir = std::env::temp_dir().join(format!("logger-span-{}", std::process::id()));
+    let config = crate::logger::LoggerConfig {
+        path: dir.clone(),
+        name: "test.log".into(),
+        file: true,
+        stdout: false,
+        level: "warn".into(),
+        ..Default::default()
+    };
+    let logger = crate::logger::Logger::new(config);
+    let subscriber = tracing_subscriber::registry().with(logger.tracing_layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let _span = tracing::info_span!("request", path = "/user").entered();
+        tracing::info!("忽略");
+        tracing::warn!("保留");
+    });
+    logger.handle().shutdown();
+
+    // 低于级别的 span 仍然附带字段
+    let log = std::fs::read_to_string(dir.join("test.log")).unwrap();
+    assert!(log.contains("保留") && log.contains("/user"));
+    assert!(!log.contains("忽略"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -1,59 +1,58 @@
 use std::{
-    io,
-    net::SocketAddr,
-    sync::mpsc::{self, Sender},
+    sync::Arc,
     task::{Context, Poll},
-    thread,
 };
 
 use axum::{
-    body::Body,
-    extract::ConnectInfo,
-    http::{header::LOCATION, Request},
+    body::{Body, HttpBody},
+    http::{
+        header::{CONTENT_LENGTH, LOCATION, REFERER, USER_AGENT},
+        HeaderMap, Request,
+    },
     response::Response,
 };
 use chrono::Local;
 use futures_util::future::BoxFuture;
 use percent_encoding::percent_decode;
 use tower::{Layer, Service};
-
-use crate::logger::{LogMsg, LoggerConfig};
+use tracing::{Instrument, Level};
+
+use crate::{
+    compare::CompareReq,
+    jsonwebtoken::Identity,
+    logger::{
+        capture,
+        writer::{self, LogSender},
+        LogField, LogMsg, LogRecord, LoggerConfig, LoggerHandle, TracingLayer,
+    },
+    middleware::{ClientIp, RequestId},
+};
 
 #[derive(Clone)]
 pub struct Logger {
-    sender: Sender<LogMsg>,
+    sender: LogSender,
+    handle: LoggerHandle,
+    level: Level,
+    config: Arc<LoggerConfig>,
 }
 
 impl Logger {
     pub fn new(config: LoggerConfig) -> Self {
-        let mut time = Local::now();
-        let mut file = config.file.then(|| config.update_log_file(&time));
-        let mut stdout = config.stdout.then(io::stdout);
-        let (sender, rx) = mpsc::channel::<LogMsg>();
-
-        thread::spawn(move || {
-            // 单独线程 同步写入日志
-            for msg in rx {
-                if let Some(stdout) = stdout.as_mut() {
-                    if let Err(err) = msg.write(&config, stdout, false) {
-                        eprintln!("写入日志失败 -> {err}")
-                    }
-                }
+        let level = config.level.parse().expect("日志级别配置错误");
+        let shared = Arc::new(config.clone());
+        config.spawn_cleaner();
+        let (sender, handle) = writer::spawn(config);
+        Self { sender, handle, level, config: shared }
+    }
 
-                if let Some(file) = file.as_mut() {
-                    // 更新日志文件
-                    if time.date_naive() != msg.begin.date_naive() {
-                        time = msg.begin;
-                        *file = config.update_log_file(&time);
-                    }
-                    if let Err(err) = msg.write(&config, file, true) {
-                        eprintln!("写入日志失败 -> {err}")
-                    }
-                }
-            }
-        });
+    /// 写入线程的句柄 关闭服务时调用 [`LoggerHandle::shutdown`] 避免丢失日志
+    pub fn handle(&self) -> LoggerHandle {
+        self.handle.clone()
+    }
 
-        Self { sender }
+    /// 将 tracing 日志写入同一个输出
+    pub fn tracing_layer(&self) -> TracingLayer {
+        TracingLayer { sender: self.sender.clone(), level: self.level }
     }
 }
 
@@ -67,19 +66,24 @@ impl<S> Layer<S> for Logger {
     type Service = LoggerService<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        LoggerService { inner, sender: self.sender.clone() }
+        LoggerService {
+            inner,
+            sender: self.sender.clone(),
+            config: self.config.clone(),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct LoggerService<S> {
     inner: S,
-    sender: Sender<LogMsg>,
+    sender: LogSender,
+    config: Arc<LoggerConfig>,
 }
 
 impl<S> Service<Request<Body>> for LoggerService<S>
 where
-    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
     S::Future: Send + 'static,
 {
     type Response = S::Response;
@@ -91,39 +95,119 @@ where
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // 排除的请求不记录
+        if self.config.excluded(req.method(), req.uri().path()) {
+            return Box::pin(self.inner.call(req));
+        }
+
         // 开始时间
         let begin = Local::now();
         // 请求方式
         let method = req.method().to_string();
         // 连接 ip
-        let ip = match req.extensions().get::<ConnectInfo<SocketAddr>>() {
-            Some(v) => v.0.ip().to_string(),
-            None => panic!("Axum service 未配置 ConnectInfo<SocketAddr>"),
+        let ip = match ClientIp::from_extensions(req.extensions()) {
+            Some(ip) => ip.to_string(),
+            None => "-".into(),
         };
+        // 请求 id
+        let request_id = req.extensions().get::<RequestId>().map(|id| id.0.clone());
         // 请求路径 解码为 utf-8
         let mut path = percent_decode(req.uri().path().as_bytes())
             .decode_utf8_lossy()
             .to_string();
 
+        // 额外字段 先记录请求的 响应的在处理完成后追加
+        let mut extra = Vec::new();
+        for field in &self.config.fields {
+            let value = match field {
+                LogField::UserAgent => header_str(req.headers(), USER_AGENT.as_str()),
+                LogField::Referer => header_str(req.headers(), REFERER.as_str()),
+                LogField::Query => req.uri().query().map(|q| capture::redact_query(q, &self.config.redact)),
+                LogField::Version => Some(format!("{:?}", req.version())),
+                LogField::ReqSize => body_size(req.headers(), req.body()),
+                LogField::ResSize => continue,
+            };
+            extra.push((field.name().to_string(), value.unwrap_or_else(|| "-".into())));
+        }
+
+        // 处理请求期间的 tracing 日志附带这些字段
+        let span = tracing::info_span!(
+            "request",
+            method = %method,
+            path = %path,
+            request_id = request_id.as_deref().unwrap_or_default(),
+        );
+
+        let capture = self.config.capture.compare_req(req.method(), req.uri().path());
         let sender = self.sender.clone();
-        let future = self.inner.call(req);
-
-        Box::pin(async move {
-            let response: Self::Response = future.await?;
+        let config = self.config.clone();
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let future = async move {
+            // 读取请求体后放回 交给后续处理
+            let req = match capture {
+                true => {
+                    let (parts, body) = req.into_parts();
+                    let (body, text) = capture::capture(body, &parts.headers, &config).await;
+                    extra.push(("req_body".to_string(), text));
+                    Request::from_parts(parts, body)
+                }
+                false => req,
+            };
+            let mut response: Self::Response = inner.call(req).await?;
             // 状态码
             let status = response.status().as_u16();
+            let end = Local::now();
+            // 按状态码、耗时过滤 成功请求采样
+            if !config.should_log(status, end - begin) {
+                return Ok(response);
+            }
             // 是否重定向
             if let Some(p) = response.headers().get(LOCATION) {
                 path.push_str(" -> ");
                 path.push_str(&percent_decode(p.as_bytes()).decode_utf8_lossy())
             }
 
-            let msg = LogMsg { begin, end: Local::now(), status, ip, method, path };
+            if config.fields.contains(&LogField::ResSize) {
+                let size = body_size(response.headers(), response.body());
+                extra.push((LogField::ResSize.name().to_string(), size.unwrap_or_else(|| "-".into())));
+            }
+            // JwtAuth 认证的用户
+            if let Some(field) = &config.identity {
+                if let Some(identity) = response.extensions().get::<Identity>() {
+                    extra.push((
+                        "identity".to_string(),
+                        identity.field(field).unwrap_or_else(|| "-".into()),
+                    ));
+                }
+            }
 
-            if let Err(err) = sender.send(msg) {
-                eprintln!("Send 日志时出现错误 {err}")
+            if capture {
+                let (parts, body) = response.into_parts();
+                let (body, text) = capture::capture(body, &parts.headers, &config).await;
+                extra.push(("res_body".to_string(), text));
+                response = Response::from_parts(parts, body);
             }
+
+            let msg = LogMsg { begin, end, status, ip, request_id, method, path, extra };
+
+            sender.send(LogRecord::Access(msg));
             Ok(response)
-        })
+        };
+
+        Box::pin(future.instrument(span))
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(Into::into)
+}
+
+/// 优先使用 Content-Length 流式响应等未知大小时为 None
+fn body_size(headers: &HeaderMap, body: &Body) -> Option<String> {
+    match headers.get(CONTENT_LENGTH) {
+        Some(len) => len.to_str().ok().map(Into::into),
+        None => body.size_hint().exact().map(|size| size.to_string()),
     }
 }
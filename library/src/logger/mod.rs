@@ -1,4 +1,12 @@
 crate::re_export! {
     mod config;
+    mod event;
+    mod filter;
+    mod format;
+    mod layer;
     mod middleware;
+    mod writer;
 }
+
+mod capture;
+mod file;
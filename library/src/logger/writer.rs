@@ -0,0 +1,154 @@
+use std::{
+    io::{self, BufWriter, Stdout, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError, SyncSender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use serde::Deserialize;
+
+use crate::logger::{file::LogFile, LogRecord, LoggerConfig};
+
+/// 日志队列满时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    /// 丢弃并计数 不影响请求
+    #[default]
+    Drop,
+    /// 等待写入线程 会阻塞处理请求的线程
+    Block,
+}
+
+enum Command {
+    Record(LogRecord),
+    Shutdown,
+}
+
+/// 发送到写入线程
+#[derive(Clone)]
+pub(crate) struct LogSender {
+    tx: SyncSender<Command>,
+    overflow: Overflow,
+    dropped: Arc<AtomicU64>,
+}
+
+impl LogSender {
+    pub fn send(&self, record: LogRecord) {
+        let sent = match self.overflow {
+            Overflow::Drop => self.tx.try_send(Command::Record(record)).is_ok(),
+            Overflow::Block => self.tx.send(Command::Record(record)).is_ok(),
+        };
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 写入线程的句柄 用于查看丢弃数量和关闭时刷新
+///
+/// ```rust,ignore
+/// let logger = Logger::new(CONFIG.logger.clone());
+/// let handle = logger.handle();
+/// axum::serve(listener, app).with_graceful_shutdown(signal).await?;
+/// handle.shutdown();
+/// ```
+#[derive(Clone)]
+pub struct LoggerHandle {
+    tx: SyncSender<Command>,
+    dropped: Arc<AtomicU64>,
+    thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl LoggerHandle {
+    /// 队列已满或已关闭而丢弃的日志数量
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 写完队列中剩余的日志 刷新缓冲并等待写入线程退出 可以重复调用
+    pub fn shutdown(&self) {
+        let Some(thread) = self.thread.lock().unwrap().take() else {
+            return;
+        };
+        if self.tx.send(Command::Shutdown).is_ok() && thread.join().is_err() {
+            eprintln!("日志写入线程异常退出")
+        }
+        let dropped = self.dropped();
+        if dropped > 0 {
+            eprintln!("日志队列已满 共丢弃 {dropped} 条日志")
+        }
+    }
+}
+
+/// 启动写入线程
+pub(crate) fn spawn(config: LoggerConfig) -> (LogSender, LoggerHandle) {
+    let (tx, rx) = mpsc::sync_channel::<Command>(config.buffer.max(1));
+    let dropped = Arc::new(AtomicU64::new(0));
+    let interval = Duration::from_millis(config.flush.max(1));
+    let overflow = config.overflow;
+
+    let mut file = config.file.then(|| LogFile::open(&config, Local::now()));
+    let mut stdout = config.stdout.then(|| BufWriter::new(io::stdout()));
+
+    let thread = thread::spawn(move || {
+        let mut last_flush = Instant::now();
+        loop {
+            let timeout = interval.saturating_sub(last_flush.elapsed());
+            match rx.recv_timeout(timeout) {
+                Ok(Command::Record(record)) => write(&config, &record, stdout.as_mut(), file.as_mut()),
+                Ok(Command::Shutdown) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if last_flush.elapsed() >= interval {
+                flush(stdout.as_mut(), file.as_mut());
+                last_flush = Instant::now();
+            }
+        }
+        // 关闭前写完已经进入队列的日志
+        while let Ok(Command::Record(record)) = rx.try_recv() {
+            write(&config, &record, stdout.as_mut(), file.as_mut());
+        }
+        flush(stdout.as_mut(), file.as_mut());
+        if let Some(file) = file.as_mut() {
+            file.join();
+        }
+    });
+
+    let sender = LogSender { tx: tx.clone(), overflow, dropped: dropped.clone() };
+    let handle = LoggerHandle { tx, dropped, thread: Arc::new(Mutex::new(Some(thread))) };
+    (sender, handle)
+}
+
+fn write(
+    config: &LoggerConfig,
+    record: &LogRecord,
+    stdout: Option<&mut BufWriter<Stdout>>,
+    file: Option<&mut LogFile>,
+) {
+    if let Some(stdout) = stdout {
+        if let Err(err) = record.write(config, stdout, false) {
+            eprintln!("写入日志失败 -> {err}")
+        }
+    }
+    // 按日期和大小更新日志文件
+    if let Some(file) = file {
+        if let Err(err) = file.write(config, record) {
+            eprintln!("写入日志失败 -> {err}")
+        }
+    }
+}
+
+fn flush(stdout: Option<&mut BufWriter<Stdout>>, file: Option<&mut LogFile>) {
+    if let Some(Err(err)) = stdout.map(|stdout| stdout.flush()) {
+        eprintln!("刷新日志失败 -> {err}")
+    }
+    if let Some(Err(err)) = file.map(|file| file.flush()) {
+        eprintln!("刷新日志失败 -> {err}")
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fmt::{Display, Formatter},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, Extensions, HeaderMap, Request},
+};
+use ipnet::IpNet;
+use serde::Deserialize;
+use tower::{Layer, Service};
+
+use crate::{reject, resp::Res};
+
+/// 客户端 ip 可以作为提取器使用
+///
+/// 使用 [`ClientIpLayer`] 时只有来自可信代理的请求才会读取转发头,
+/// 否则直接使用连接地址
+///
+/// ```rust,ignore
+/// async fn handler(ClientIp(ip): ClientIp) -> String {
+///     ip.to_string()
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// [`ClientIpLayer`] 解析的 ip, 没有时使用连接地址
+    pub fn from_extensions(extensions: &Extensions) -> Option<Self> {
+        extensions.get::<ClientIp>().copied().or_else(|| {
+            let ConnectInfo(addr) = extensions.get::<ConnectInfo<SocketAddr>>()?;
+            Some(Self(addr.ip()))
+        })
+    }
+
+    /// 从右往左跳过可信代理 第一个不可信的地址就是客户端
+    ///
+    /// 只读取 header 指定的请求头, 其他转发头可能是客户端伪造的
+    pub fn resolve(headers: &HeaderMap, peer: IpAddr, trusted: &[IpNet], header: ForwardedHeader) -> Self {
+        let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+        if !is_trusted(&peer) {
+            return Self(peer);
+        }
+
+        let chain = match header {
+            ForwardedHeader::XForwardedFor => x_forwarded_for(headers),
+            ForwardedHeader::Forwarded => forwarded(headers),
+            ForwardedHeader::XRealIp => x_real_ip(headers),
+        };
+        let mut client = peer;
+        for hop in chain.unwrap_or_default().into_iter().rev() {
+            // unknown 或无法解析的地址 之后的都不可信
+            let Some(ip) = hop else { break };
+            client = ip;
+            if !is_trusted(&ip) {
+                break;
+            }
+        }
+        Self(client)
+    }
+}
+
+/// 可信代理写入客户端地址的请求头 需要和代理的配置一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForwardedHeader {
+    /// nginx、ALB 等常用的 X-Forwarded-For
+    #[default]
+    XForwardedFor,
+    /// RFC 7239 Forwarded
+    Forwarded,
+    /// 代理覆盖写入的 X-Real-IP
+    XRealIp,
+}
+
+impl Display for ClientIp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// 多个同名请求头按顺序拼接
+fn header_list(headers: &HeaderMap, name: &str) -> Option<Vec<String>> {
+    let values = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim().to_string())
+        .collect::<Vec<_>>();
+    (!values.is_empty()).then_some(values)
+}
+
+fn x_real_ip(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let ip = headers.get("x-real-ip")?.to_str().ok()?;
+    Some(vec![parse_node(ip.trim())])
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let list = header_list(headers, "x-forwarded-for")?;
+    Some(list.iter().map(|ip| parse_node(ip)).collect())
+}
+
+/// RFC 7239 `Forwarded: for=192.0.2.60;proto=http, for="[2001:db8::1]:4711"`
+fn forwarded(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let list = header_list(headers, "forwarded")?;
+    let chain = list
+        .iter()
+        .map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("for")
+                    .then(|| parse_node(value.trim_matches('"')))
+            })?
+        })
+        .collect();
+    Some(chain)
+}
+
+/// 支持 `1.2.3.4` `1.2.3.4:80` `2001:db8::1` `[2001:db8::1]:80`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
+
+/// 解析客户端 ip 写入 Extension
+///
+/// 需要放在 [`crate::logger::Logger`] 外层日志才能记录到解析后的 ip
+///
+/// ```rust,ignore
+/// Router::new()
+///     .layer(Logger::default())
+///     .layer(ClientIpLayer::new(vec!["10.0.0.0/8".parse()?]))
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ClientIpLayer {
+    trusted: Arc<Vec<IpNet>>,
+    header: ForwardedHeader,
+}
+
+impl ClientIpLayer {
+    /// trusted 为可信代理的网段 默认读取 X-Forwarded-For
+    pub fn new(trusted: Vec<IpNet>) -> Self {
+        Self {
+            trusted: Arc::new(trusted),
+            header: ForwardedHeader::default(),
+        }
+    }
+
+    /// 读取客户端地址的请求头
+    pub fn header(mut self, header: ForwardedHeader) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+impl<S> Layer<S> for ClientIpLayer {
+    type Service = ClientIpService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientIpService { inner, trusted: self.trusted.clone(), header: self.header }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientIpService<S> {
+    inner: S,
+    trusted: Arc<Vec<IpNet>>,
+    header: ForwardedHeader,
+}
+
+impl<S> Service<Request<Body>> for ClientIpService<S>
+where
+    S: Service<Request<Body>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        if let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>() {
+            let ip = ClientIp::resolve(req.headers(), addr.ip(), &self.trusted, self.header);
+            req.extensions_mut().insert(ip);
+        }
+        self.inner.call(req)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Res<()>;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        match ClientIp::from_extensions(&parts.extensions) {
+            Some(ip) => Ok(ip),
+            None => reject!(400, "获取连接 ip 失败"),
+        }
+    }
+}
+
+#[test]
+fn resolve_t() {
+    use axum::http::HeaderValue;
+
+    let trusted = vec!["10.0.0.0/8".parse().unwrap()];
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    let resolve = |pairs: &[(&'static str, &'static str)], peer: IpAddr, header: ForwardedHeader| {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        ClientIp::resolve(&headers, peer, &trusted, header).to_string()
+    };
+    let xff = ForwardedHeader::XForwardedFor;
+
+    // 不可信的连接忽略转发头
+    assert_eq!(
+        resolve(&[("x-forwarded-for", "1.1.1.1")], "2.2.2.2".parse().unwrap(), xff),
+        "2.2.2.2"
+    );
+    // 伪造的左侧地址被忽略
+    assert_eq!(
+        resolve(&[("x-forwarded-for", "6.6.6.6, 1.1.1.1, 10.0.0.2")], proxy, xff),
+        "1.1.1.1"
+    );
+    // 代理只追加 X-Forwarded-For 时 客户端伪造的 Forwarded、X-Real-IP 不生效
+    let spoofed = [
+        ("forwarded", "for=6.6.6.6"),
+        ("x-real-ip", "6.6.6.6"),
+        ("x-forwarded-for", "1.1.1.1"),
+    ];
+    assert_eq!(resolve(&spoofed, proxy, xff), "1.1.1.1");
+    assert_eq!(resolve(&[("x-real-ip", "6.6.6.6")], proxy, xff), "10.0.0.1");
+
+    assert_eq!(
+        resolve(&[("x-real-ip", "1.1.1.1")], proxy, ForwardedHeader::XRealIp),
+        "1.1.1.1"
+    );
+    assert_eq!(
+        resolve(
+            &[("forwarded", r#"for=1.1.1.1;proto=http, for="[2001:db8::1]:4711""#)],
+            proxy,
+            ForwardedHeader::Forwarded
+        ),
+        "2001:db8::1"
+    );
+    assert_eq!(
+        resolve(&[("forwarded", "for=unknown")], proxy, ForwardedHeader::Forwarded),
+        "10.0.0.1"
+    );
+}
@@ -1 +1,5 @@
-
+crate::re_export! {
+    mod client_ip;
+    mod negotiate;
+    mod request_id;
+}
@@ -0,0 +1,64 @@
+use std::task::{Context, Poll};
+
+use axum::{
+    body::Body,
+    http::{
+        header::{ACCEPT, VARY},
+        HeaderValue, Request,
+    },
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::resp::Format;
+
+/// 按请求头 Accept 选择 [`crate::resp::Res`] 的序列化格式
+///
+/// ```rust,ignore
+/// Router::new()
+///     .route("/user", get(user))
+///     .layer(NegotiateLayer)
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NegotiateLayer;
+
+impl<S> Layer<S> for NegotiateLayer {
+    type Service = NegotiateService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NegotiateService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NegotiateService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for NegotiateService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let format = Format::from_accept(req.headers());
+        let future = format.scope(self.inner.call(req));
+        Box::pin(async move {
+            let mut response = future.await?;
+            // 响应内容随 Accept 变化 缓存需要区分
+            response
+                .headers_mut()
+                .append(VARY, HeaderValue::from_static(ACCEPT.as_str()));
+            Ok(response)
+        })
+    }
+}
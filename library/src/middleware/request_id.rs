@@ -0,0 +1,173 @@
+use std::{
+    fmt::{Display, Formatter},
+    task::{Context, Poll},
+};
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderName, HeaderValue, Request},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+use crate::{reject, resp::Res};
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// 处理请求期间可以获取的请求信息
+#[derive(Clone)]
+struct Current {
+    id: RequestId,
+    path: String,
+}
+
+tokio::task_local! {
+    static CURRENT: Current;
+}
+
+/// 请求 id 可以作为提取器使用
+///
+/// ```rust,ignore
+/// async fn handler(id: RequestId) -> String {
+///     id.to_string()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// 当前请求的 id, 不在 [`RequestIdLayer`] 处理的请求中时返回 None
+    pub fn current() -> Option<RequestId> {
+        CURRENT.try_with(|current| current.id.clone()).ok()
+    }
+
+    /// 当前请求的路径 用于 problem 的 instance
+    pub(crate) fn current_path() -> Option<String> {
+        CURRENT.try_with(|current| current.path.clone()).ok()
+    }
+
+    /// 客户端传入的 id 长度不超过 128 且只包含可见 ASCII 字符才会使用
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let id = value.to_str().ok()?;
+        let valid = !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(id.to_string()))
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = Res<()>;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<RequestId>() {
+            Some(id) => Ok(id.clone()),
+            None => reject!(500, "未配置 RequestIdLayer"),
+        }
+    }
+}
+
+/// 为每个请求分配 id
+///
+/// 优先使用请求头 x-request-id, 没有时生成 UUID。
+/// id 写入请求的 Extension 和响应头, 处理请求期间可以通过 [`RequestId::current`] 获取。
+///
+/// 需要放在 [`crate::logger::Logger`] 外层日志才能记录到 id
+///
+/// ```rust,ignore
+/// Router::new()
+///     .layer(Logger::default())
+///     .layer(RequestIdLayer)
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for RequestIdService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let id = req
+            .headers()
+            .get(X_REQUEST_ID)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(|| RequestId(Uuid::new_v4().to_string()));
+        req.extensions_mut().insert(id.clone());
+
+        let current = Current { id: id.clone(), path: req.uri().path().to_string() };
+        let future = CURRENT.scope(current, self.inner.call(req));
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Ok(value) = HeaderValue::from_str(&id.0) {
+                response.headers_mut().insert(X_REQUEST_ID, value);
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[tokio::test]
+async fn request_id_t() {
+    use axum::{body, routing::get, Router};
+    use tower::ServiceExt;
+
+    let app = Router::new()
+        .route("/", get(|| async { reject!(404, "用户不存在") as Result<(), Res> }))
+        .layer(RequestIdLayer);
+    let call = |id: Option<&str>| {
+        let mut req = Request::builder().uri("/");
+        if let Some(id) = id {
+            req = req.header(X_REQUEST_ID, id);
+        }
+        app.clone().oneshot(req.body(Body::empty()).unwrap())
+    };
+
+    // 合法的 id 原样返回
+    let res = call(Some("abc-123")).await.unwrap();
+    assert_eq!(res.headers()[X_REQUEST_ID], "abc-123");
+
+    // 过长或包含不可见字符时重新生成
+    for id in ["a".repeat(129), "a b".into()] {
+        let res = call(Some(&id)).await.unwrap();
+        let header = res.headers()[X_REQUEST_ID].to_str().unwrap();
+        assert!(header != id && Uuid::parse_str(header).is_ok());
+    }
+
+    // 错误响应体中带有 request_id
+    let res = call(None).await.unwrap();
+    let id = res.headers()[X_REQUEST_ID].to_str().unwrap().to_string();
+    let bytes = body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(json["request_id"], id.as_str());
+}
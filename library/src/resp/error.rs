@@ -0,0 +1,257 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Write},
+};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::multipart::MultipartError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use validator::ValidationErrors;
+
+use crate::resp::Res;
+
+/// 错误类别 对应稳定的错误码和 HTTP 状态码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    BadRequest,
+    /// 数据验证失败
+    Validation,
+    Unauthorized,
+    TokenExpired,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    /// 数据库连接池等依赖暂不可用
+    Unavailable,
+    Internal,
+}
+
+impl ErrorKind {
+    /// 返回给客户端的错误码 不随提示信息变化
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::BadRequest => "bad_request",
+            ErrorKind::Validation => "validation_failed",
+            ErrorKind::Unauthorized => "unauthorized",
+            ErrorKind::TokenExpired => "token_expired",
+            ErrorKind::Forbidden => "forbidden",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Conflict => "conflict",
+            ErrorKind::PayloadTooLarge => "payload_too_large",
+            ErrorKind::Unavailable => "service_unavailable",
+            ErrorKind::Internal => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorKind::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorKind::Validation => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorKind::Unauthorized | ErrorKind::TokenExpired => StatusCode::UNAUTHORIZED,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Conflict => StatusCode::CONFLICT,
+            ErrorKind::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// 没有指定提示信息时使用
+    pub fn message(&self) -> &'static str {
+        match self {
+            ErrorKind::BadRequest => "请求错误",
+            ErrorKind::Validation => "数据验证失败",
+            ErrorKind::Unauthorized => "身份认证失败",
+            ErrorKind::TokenExpired => "身份认证失败: 令牌已过期",
+            ErrorKind::Forbidden => "权限不足",
+            ErrorKind::NotFound => "资源不存在",
+            ErrorKind::Conflict => "资源冲突",
+            ErrorKind::PayloadTooLarge => "请求体过大",
+            ErrorKind::Unavailable => "服务暂不可用",
+            ErrorKind::Internal => "服务器内部错误",
+        }
+    }
+}
+
+/// 应用错误 message 返回给客户端, cause 只写入日志
+///
+/// ```rust,ignore
+/// async fn demo(PgConn(mut conn): PgConn) -> Resp<User> {
+///     // diesel、连接池等错误自动转换 不会把内部错误返回给客户端
+///     let user = users.find(id).first(&mut conn).await?;
+///     if user.disabled {
+///         return Err(AppError::new(ErrorKind::Forbidden, "账号已禁用").into());
+///     }
+///     resolve!(200 => user, "ok")
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AppError {
+    kind: ErrorKind,
+    message: String,
+    cause: Option<anyhow::Error>,
+    extensions: Map<String, Value>,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Display) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            cause: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// 内部错误 使用默认提示信息
+    pub fn internal(cause: impl Into<anyhow::Error>) -> Self {
+        Self::from(ErrorKind::Internal).with_cause(cause)
+    }
+
+    /// 记录到日志的原因
+    pub fn with_cause(mut self, cause: impl Into<anyhow::Error>) -> Self {
+        self.cause = Some(cause.into());
+        self
+    }
+
+    /// problem 格式的扩展字段
+    pub fn with_extension(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        self.extensions
+            .insert(key.into(), serde_json::to_value(value).unwrap_or_default());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn cause(&self) -> Option<&anyhow::Error> {
+        self.cause.as_ref()
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind.code(), self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<ErrorKind> for AppError {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind, kind.message())
+    }
+}
+
+/// 可以转换为 [`AppError`] 的错误都能在返回 [`crate::resp::Resp`] 的函数中使用 `?`
+impl<E: Into<AppError>> From<E> for Res {
+    fn from(err: E) -> Self {
+        let err = err.into();
+        if let Some(cause) = &err.cause {
+            match err.kind.status().is_server_error() {
+                true => tracing::error!(code = err.kind.code(), "{}: {cause:#}", err.message),
+                false => tracing::warn!(code = err.kind.code(), "{}: {cause:#}", err.message),
+            }
+        }
+        let mut res = Res::new(err.kind.status().as_u16(), err.message, ());
+        res.error = Some(err.kind.code());
+        res.extensions = err.extensions;
+        res
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        Res::from(self).into_response()
+    }
+}
+
+/// 未分类的错误都作为内部错误 不返回原因
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::internal(err)
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(err: ValidationErrors) -> Self {
+        let mut msg = String::from("数据验证失败: ");
+        // problem 格式中按字段列出 {"errors": {"email": ["邮箱格式不正确"]}}
+        let mut errors = BTreeMap::new();
+        for (key, value) in err.field_errors() {
+            write!(msg, "{key}<").unwrap();
+            value
+                .iter()
+                .map(|m| &m.code)
+                .for_each(|field| write!(msg, "{field}, ").unwrap());
+            msg.replace_range(msg.len() - 2.., ">; ");
+            errors.insert(key, value.iter().map(|m| m.code.clone()).collect::<Vec<_>>());
+        }
+        msg.pop();
+        Self::new(ErrorKind::Validation, msg).with_extension("errors", errors)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind as JwtKind;
+        match err.kind() {
+            JwtKind::ExpiredSignature => ErrorKind::TokenExpired.into(),
+            _ => Self::new(ErrorKind::Unauthorized, format!("身份认证失败: {err}")),
+        }
+    }
+}
+
+impl From<MultipartError> for AppError {
+    fn from(err: MultipartError) -> Self {
+        let kind = match err.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorKind::PayloadTooLarge,
+            _ => ErrorKind::BadRequest,
+        };
+        Self::new(kind, err.body_text())
+    }
+}
+
+/// 获取连接池连接失败
+#[cfg(feature = "database")]
+impl<E: std::error::Error + Send + Sync + 'static> From<bb8::RunError<E>> for AppError {
+    fn from(err: bb8::RunError<E>) -> Self {
+        Self::from(ErrorKind::Unavailable).with_cause(err)
+    }
+}
+
+#[cfg(feature = "database")]
+impl From<diesel::result::Error> for AppError {
+    fn from(err: diesel::result::Error) -> Self {
+        use diesel::result::{DatabaseErrorKind, Error};
+        match err {
+            Error::NotFound => ErrorKind::NotFound.into(),
+            Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                Self::from(ErrorKind::Conflict).with_cause(err)
+            }
+            err => Self::internal(err),
+        }
+    }
+}
+
+#[test]
+fn app_error_t() {
+    let res = Res::from(AppError::internal(anyhow::anyhow!("connection refused")));
+    assert_eq!(res.code, 500);
+    assert_eq!(res.info, "服务器内部错误");
+    assert_eq!(res.error, Some("internal_error"));
+
+    let res = Res::from(AppError::new(ErrorKind::NotFound, "用户不存在"));
+    assert_eq!((res.code, res.info.as_str()), (404, "用户不存在"));
+}
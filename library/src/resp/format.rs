@@ -0,0 +1,133 @@
+use std::future::Future;
+
+use axum::http::{header::ACCEPT, HeaderMap};
+use serde::{de::DeserializeOwned, Serialize};
+
+tokio::task_local! {
+    static FORMAT: Format;
+}
+
+/// 请求体和响应体的序列化格式
+///
+/// msgpack 和 cbor 分别需要开启同名 feature
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Format {
+    /// 按优先级排列 Accept 为通配符时使用靠前的格式
+    const ALL: &'static [Format] = &[
+        Format::Json,
+        #[cfg(feature = "msgpack")]
+        Format::MsgPack,
+        #[cfg(feature = "cbor")]
+        Format::Cbor,
+    ];
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "application/cbor",
+        }
+    }
+
+    /// 当前请求协商的格式, 不在 [`crate::middleware::NegotiateLayer`] 处理的请求中时为 Json
+    pub fn current() -> Format {
+        FORMAT.try_with(|format| *format).unwrap_or_default()
+    }
+
+    /// 处理请求期间使用这个格式
+    pub(crate) fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        FORMAT.scope(self, future)
+    }
+
+    /// 忽略参数 如 `application/json; charset=utf-8`
+    pub fn from_content_type(content_type: &str) -> Option<Format> {
+        let mime = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "application/json" => Some(Format::Json),
+            #[cfg(feature = "msgpack")]
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Format::MsgPack),
+            #[cfg(feature = "cbor")]
+            "application/cbor" => Some(Format::Cbor),
+            _ => None,
+        }
+    }
+
+    /// 按 Accept 的 q 值选择 没有可用的格式时为 Json
+    pub fn from_accept(headers: &HeaderMap) -> Format {
+        let mut best = (Format::Json, 0.0);
+        let ranges = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+        for range in ranges {
+            let mut params = range.split(';');
+            let mime = params.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let q = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let format = match mime.as_str() {
+                "*/*" | "application/*" => Some(Format::ALL[0]),
+                mime => Format::from_content_type(mime),
+            };
+            if let Some(format) = format {
+                if q > best.1 {
+                    best = (format, q);
+                }
+            }
+        }
+        best.0
+    }
+
+    pub fn encode(&self, value: &impl Serialize) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Format::Json => serde_json::to_vec(value)?,
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => rmp_serde::to_vec_named(value)?,
+            #[cfg(feature = "cbor")]
+            Format::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::into_writer(value, &mut buf)?;
+                buf
+            }
+        })
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> anyhow::Result<T> {
+        Ok(match self {
+            Format::Json => serde_json::from_slice(bytes)?,
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => rmp_serde::from_slice(bytes)?,
+            #[cfg(feature = "cbor")]
+            Format::Cbor => ciborium::from_reader(bytes)?,
+        })
+    }
+}
+
+#[test]
+fn format_t() {
+    let accept = |value: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, value.parse().unwrap());
+        Format::from_accept(&headers)
+    };
+    assert_eq!(Format::from_accept(&HeaderMap::new()), Format::Json);
+    assert_eq!(accept("text/html, */*;q=0.8"), Format::Json);
+    assert_eq!(accept("application/xml"), Format::Json);
+    #[cfg(feature = "msgpack")]
+    assert_eq!(accept("application/json;q=0.5, application/msgpack"), Format::MsgPack);
+    #[cfg(feature = "cbor")]
+    assert_eq!(accept("application/cbor, application/json;q=0.9"), Format::Cbor);
+}
@@ -0,0 +1,195 @@
+//! ```rust,ignore
+//! async fn demo() -> Resp<String> {
+//!     let data = /*handle*/.map_err(|err|res!(400,"{err}"))?;
+//!
+//!     if let Err(err) = /*handle*/ {
+//!        reject(400, "{err}")
+//!     }else {
+//!        resolve!(200 => data, "ok")
+//!     }
+//! }
+//!
+//! // 业务码和 HTTP 状态码分开 业务码不是有效的状态码时为 200
+//! async fn pay() -> Resp<()> {
+//!     reject!(status = StatusCode::PAYMENT_REQUIRED, 10023, "余额不足")
+//! }
+//!
+//! // AppError 和可以转换为 AppError 的错误可以直接使用 ?
+//! async fn find(PgConn(mut conn): PgConn) -> Resp<User> {
+//!     let user = users.first(&mut conn).await?;
+//!     resolve!(200 => user, "ok")
+//! }
+//! ```
+
+crate::re_export! {
+    mod error;
+    mod format;
+    mod page;
+    mod problem;
+}
+
+use std::fmt::Display;
+
+use axum::{
+    http::{header::CONTENT_TYPE, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::middleware::RequestId;
+
+pub type Resp<T> = std::result::Result<Res<T>, Res<()>>;
+pub type Result<T> = std::result::Result<T, Res<()>>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Res<T: Serialize = ()> {
+    /// 业务码
+    pub code: u16,
+    /// HTTP 状态码
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub info: String,
+    pub data: T,
+    /// [`ErrorKind::code`] 错误码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+    /// 为空时使用 [`ResConfig`] 的全局配置
+    #[serde(skip)]
+    pub(crate) style: Option<ResStyle>,
+    /// problem 的扩展字段
+    #[serde(skip)]
+    pub(crate) extensions: Map<String, Value>,
+}
+
+impl<T: Serialize> Res<T> {
+    /// code 为 100-599 时同时作为 HTTP 状态码, 否则状态码为 200
+    pub fn new(code: u16, msg: impl Display, data: impl Into<T>) -> Self {
+        let status = match code {
+            100..=599 => StatusCode::from_u16(code).unwrap_or_default(),
+            _ => StatusCode::OK,
+        };
+        Self {
+            code,
+            status,
+            info: msg.to_string(),
+            data: data.into(),
+            error: None,
+            style: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// 只修改 HTTP 状态码
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// 只修改业务码
+    pub fn code(mut self, code: u16) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// 单独指定这个响应的格式
+    pub fn style(mut self, style: ResStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// 添加 problem 的扩展字段 Envelope 格式不输出
+    pub fn extension(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        self.extensions
+            .insert(key.into(), serde_json::to_value(value).unwrap_or_default());
+        self
+    }
+}
+
+/// 错误响应附带请求 id 方便排查
+#[derive(Serialize)]
+struct ErrorBody<'a, T: Serialize> {
+    #[serde(flatten)]
+    res: &'a Res<T>,
+    request_id: String,
+}
+
+impl<T: Serialize> IntoResponse for Res<T> {
+    fn into_response(self) -> Response {
+        let config = ResConfig::global();
+        let style = self.style.unwrap_or(config.style);
+        // problem 格式只有 JSON
+        let format = Format::current();
+        let is_error = self.status.as_u16() >= 400 || self.code >= 400;
+        let (content_type, body) = match RequestId::current() {
+            _ if style == ResStyle::Problem && self.status.as_u16() >= 400 => (
+                "application/problem+json",
+                Format::Json.encode(&Problem::new(&self, config)),
+            ),
+            Some(id) if is_error => (
+                format.mime(),
+                format.encode(&ErrorBody { res: &self, request_id: id.0 }),
+            ),
+            _ => (format.mime(), format.encode(&self)),
+        };
+        let body = match body {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::error!("序列化响应失败: {err}");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        let mut response = Response::new(body.into());
+        *response.status_mut() = self.status;
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        response
+    }
+}
+
+#[macro_export]
+macro_rules! res {
+    (status = $status:expr, $code:expr => $data:expr, $($msg:tt)+) => {
+        $crate::resp::Res::new($code, format!($($msg)+), $data).status($status)
+    };
+    (status = $status:expr, $code:expr, $($msg:tt)+) => {
+        $crate::resp::Res::new($code, format!($($msg)+), ()).status($status) as $crate::resp::Res
+    };
+    ($code:expr, $($msg:tt)+) => {
+        $crate::resp::Res::new($code, format!($($msg)+), ()) as $crate::resp::Res
+    };
+    ($code:expr => $data:expr, $($msg:tt)+) => {
+        $crate::resp::Res::new($code, format!($($msg)+), $data)
+    };
+}
+
+#[macro_export]
+macro_rules! reject {
+    ($($t:tt)*) => {
+        Err($crate::res!($($t)*))
+    };
+}
+
+#[macro_export]
+macro_rules! resolve {
+    ($($t:tt)*) => {
+        Ok($crate::res!($($t)*))
+    };
+}
+
+#[test]
+fn res_t() {
+    let res = res!(404, "用户不存在");
+    assert_eq!((res.code, res.status), (404, StatusCode::NOT_FOUND));
+
+    // 业务码不是状态码时不会 panic
+    let res = res!(10023, "余额不足");
+    assert_eq!(res.into_response().status(), StatusCode::OK);
+
+    let res = res!(status = StatusCode::PAYMENT_REQUIRED, 10023 => 5, "余额不足");
+    assert_eq!(
+        (res.code, res.status, res.data),
+        (10023, StatusCode::PAYMENT_REQUIRED, 5)
+    );
+}
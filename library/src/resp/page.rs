@@ -0,0 +1,260 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::LINK, request::Parts, HeaderMap, HeaderValue, Uri},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{reject, res, resp::Res};
+
+/// 分页配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct PageConfig {
+    /// 没有传 size 时使用
+    #[serde(default = "default_size")]
+    pub default_size: u64,
+    #[serde(default = "default_max_size")]
+    pub max_size: u64,
+    /// 游标签名秘钥 未设置时随机生成, 重启后之前的游标失效
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+crate::gen_default! {
+    default_size, 20, u64;
+    default_max_size, 100, u64;
+}
+
+impl Default for PageConfig {
+    fn default() -> Self {
+        Self {
+            default_size: default_size(),
+            max_size: default_max_size(),
+            secret: None,
+        }
+    }
+}
+
+static PAGE_CONFIG: OnceCell<PageConfig> = OnceCell::new();
+
+static CURSOR_KEY: Lazy<Vec<u8>> = Lazy::new(|| match &PageConfig::global().secret {
+    Some(secret) => secret.as_bytes().to_vec(),
+    None => rand::random::<[u8; 32]>().to_vec(),
+});
+
+impl PageConfig {
+    /// 设置全局配置 只有第一次调用生效
+    pub fn init(self) {
+        let _ = PAGE_CONFIG.set(self);
+    }
+
+    pub(crate) fn global() -> &'static PageConfig {
+        PAGE_CONFIG.get_or_init(PageConfig::default)
+    }
+}
+
+fn sign(payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&CURSOR_KEY).expect("HMAC 可以使用任意长度的秘钥");
+    mac.update(payload);
+    mac
+}
+
+/// 生成签名的游标 客户端无法修改其中的内容
+pub fn encode_cursor(value: &impl Serialize) -> String {
+    let payload = serde_json::to_vec(value).unwrap_or_default();
+    let signature = sign(&payload).finalize().into_bytes();
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(&payload),
+        URL_SAFE_NO_PAD.encode(signature)
+    )
+}
+
+/// 签名不正确时返回 None
+pub fn decode_cursor<C: DeserializeOwned>(cursor: &str) -> Option<C> {
+    let (payload, signature) = cursor.split_once('.')?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    sign(&payload).verify_slice(&signature).ok()?;
+    serde_json::from_slice(&payload).ok()
+}
+
+/// 提取并验证分页参数 `?page=2&size=20` 或 `?cursor=xxx&size=20`
+///
+/// C 为游标的内容 如上一页最后一条的 id, 传了 cursor 时忽略 page
+///
+/// ```rust,ignore
+/// async fn list(query: VPage, OriginalUri(uri): OriginalUri) -> Result<impl IntoResponse, Res> {
+///     let (users, total) = find_users(query.offset(), query.size).await?;
+///     let page = Page::new(users, total, &query);
+///     Ok((page.link(&uri), res!(200 => page, "ok")))
+/// }
+///
+/// // 游标分页 多查询一条判断是否还有下一页
+/// async fn feed(query: VPage<i64>) -> Resp<Page<Post>> {
+///     let posts = find_posts_after(query.cursor.unwrap_or(0), query.size + 1).await?;
+///     resolve!(200 => Page::cursor(posts, &query, |post| post.id), "ok")
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VPage<C = ()> {
+    /// 从 1 开始
+    pub page: u64,
+    pub size: u64,
+    pub cursor: Option<C>,
+}
+
+impl<C> VPage<C> {
+    pub fn offset(&self) -> u64 {
+        (self.page - 1).saturating_mul(self.size)
+    }
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    page: Option<u64>,
+    size: Option<u64>,
+    cursor: Option<String>,
+}
+
+#[async_trait]
+impl<S, C> FromRequestParts<S> for VPage<C>
+where
+    S: Send + Sync,
+    C: DeserializeOwned + Send,
+{
+    type Rejection = Res<()>;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let query: PageQuery = serde_urlencoded::from_str(query).map_err(|err| res!(422, "{err}"))?;
+        let config = PageConfig::global();
+
+        let size = query.size.unwrap_or(config.default_size);
+        if size == 0 || size > config.max_size {
+            return reject!(422, "数据验证失败: size<1-{}>", config.max_size);
+        }
+        let page = query.page.unwrap_or(1);
+        if page == 0 {
+            return reject!(422, "数据验证失败: page<从 1 开始>");
+        }
+        let cursor = match query.cursor {
+            Some(cursor) => Some(decode_cursor(&cursor).ok_or_else(|| res!(422, "数据验证失败: cursor<无效的游标>"))?),
+            None => None,
+        };
+        Ok(Self { page, size, cursor })
+    }
+}
+
+/// 分页列表 作为 [`Res`] 的 data
+///
+/// 游标分页没有 total 和 page
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T: Serialize> {
+    pub items: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+    pub size: u64,
+    pub has_next: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T: Serialize> Page<T> {
+    /// 偏移分页
+    pub fn new<C>(items: Vec<T>, total: u64, query: &VPage<C>) -> Self {
+        Self {
+            items,
+            total: Some(total),
+            page: Some(query.page),
+            size: query.size,
+            has_next: query.page.saturating_mul(query.size) < total,
+            next_cursor: None,
+        }
+    }
+
+    /// 游标分页 items 需要多查询一条用于判断是否还有下一页, next 从本页最后一条生成下一页的游标
+    pub fn cursor<C, N: Serialize>(mut items: Vec<T>, query: &VPage<C>, next: impl FnOnce(&T) -> N) -> Self {
+        let has_next = items.len() as u64 > query.size;
+        items.truncate(query.size as usize);
+        let next_cursor = match has_next {
+            true => items.last().map(|item| encode_cursor(&next(item))),
+            false => None,
+        };
+        Self {
+            items,
+            total: None,
+            page: None,
+            size: query.size,
+            has_next,
+            next_cursor,
+        }
+    }
+
+    /// RFC 8288 Link 响应头 保留请求中的其他查询参数
+    ///
+    /// 嵌套路由中 [`axum::extract::Uri`] 去掉了前缀, 需要传入 [`axum::extract::OriginalUri`]
+    pub fn link(&self, uri: &Uri) -> HeaderMap {
+        let mut params =
+            serde_urlencoded::from_str::<Vec<(String, String)>>(uri.query().unwrap_or_default()).unwrap_or_default();
+        params.retain(|(k, _)| !matches!(k.as_str(), "page" | "size" | "cursor"));
+        let href = |key: &str, value: String, rel: &str| {
+            let mut params = params.clone();
+            params.push((key.into(), value));
+            params.push(("size".into(), self.size.to_string()));
+            let query = serde_urlencoded::to_string(params).unwrap_or_default();
+            format!("<{}?{query}>; rel=\"{rel}\"", uri.path())
+        };
+
+        let mut links = Vec::new();
+        if let (Some(page), Some(total)) = (self.page, self.total) {
+            let last = total.div_ceil(self.size).max(1);
+            links.push(href("page", "1".into(), "first"));
+            if page > 1 {
+                links.push(href("page", (page - 1).min(last).to_string(), "prev"));
+            }
+            if self.has_next {
+                links.push(href("page", (page + 1).to_string(), "next"));
+            }
+            links.push(href("page", last.to_string(), "last"));
+        }
+        if let Some(cursor) = &self.next_cursor {
+            links.push(href("cursor", cursor.clone(), "next"));
+        }
+
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+            if !links.is_empty() {
+                headers.insert(LINK, value);
+            }
+        }
+        headers
+    }
+}
+
+#[test]
+fn page_t() {
+    let cursor = encode_cursor(&42_i64);
+    assert_eq!(decode_cursor::<i64>(&cursor), Some(42));
+    assert_eq!(decode_cursor::<i64>(&cursor.replace('.', "x.")), None);
+
+    let query = VPage::<i64> { page: 1, size: 2, cursor: None };
+    let page = Page::cursor(vec![1, 2, 3], &query, |id| *id);
+    assert_eq!((page.items.len(), page.has_next), (2, true));
+    assert_eq!(page.next_cursor.as_deref().and_then(decode_cursor), Some(2));
+
+    let query = VPage::<()> { page: 2, size: 10, cursor: None };
+    let page = Page::new(vec![0; 10], 35, &query);
+    let uri: Uri = "/users?name=a&page=2".parse().unwrap();
+    assert_eq!(
+        page.link(&uri)[LINK],
+        "</users?name=a&page=1&size=10>; rel=\"first\", </users?name=a&page=1&size=10>; rel=\"prev\", \
+         </users?name=a&page=3&size=10>; rel=\"next\", </users?name=a&page=4&size=10>; rel=\"last\""
+    );
+}
@@ -0,0 +1,113 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{middleware::RequestId, resp::Res};
+
+/// 错误响应的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResStyle {
+    /// `{code, info, data}`
+    #[default]
+    Envelope,
+    /// RFC 7807 `application/problem+json` 成功响应仍然使用 Envelope
+    Problem,
+}
+
+/// 全局响应配置
+///
+/// ```rust,ignore
+/// ResConfig { style: ResStyle::Problem, problem_type: None }.init();
+///
+/// // 单个响应覆盖全局配置
+/// res!(404, "用户不存在").style(ResStyle::Envelope)
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResConfig {
+    #[serde(default)]
+    pub style: ResStyle,
+    /// problem 的 type 前缀 如 "https://example.com/problems/" 后面拼接错误码,
+    /// 未设置或没有错误码时为 about:blank
+    #[serde(default)]
+    pub problem_type: Option<String>,
+}
+
+static RES_CONFIG: OnceCell<ResConfig> = OnceCell::new();
+
+impl ResConfig {
+    /// 设置全局配置 只有第一次调用生效
+    pub fn init(self) {
+        let _ = RES_CONFIG.set(self);
+    }
+
+    pub(crate) fn global() -> &'static ResConfig {
+        RES_CONFIG.get_or_init(ResConfig::default)
+    }
+}
+
+/// problem details 响应体
+#[derive(Serialize)]
+pub(crate) struct Problem<'a> {
+    #[serde(rename = "type")]
+    kind: String,
+    title: &'static str,
+    status: u16,
+    detail: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    /// 和状态码不同的业务码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    #[serde(flatten)]
+    extensions: &'a Map<String, Value>,
+}
+
+impl<'a> Problem<'a> {
+    /// instance 为请求路径 需要 [`crate::middleware::RequestIdLayer`]
+    pub(crate) fn new<T: Serialize>(res: &'a Res<T>, config: &ResConfig) -> Self {
+        let kind = match (&config.problem_type, res.error) {
+            (Some(prefix), Some(code)) => format!("{prefix}{code}"),
+            _ => "about:blank".into(),
+        };
+        let status = res.status.as_u16();
+        let title = res.status.canonical_reason().unwrap_or("Error");
+        Self {
+            kind,
+            title,
+            status,
+            detail: &res.info,
+            instance: RequestId::current_path(),
+            code: res.error,
+            app_code: (res.code != status).then_some(res.code),
+            request_id: RequestId::current().map(|id| id.0),
+            extensions: &res.extensions,
+        }
+    }
+}
+
+#[test]
+fn problem_t() {
+    use crate::resp::{AppError, ErrorKind};
+
+    let config = ResConfig {
+        style: ResStyle::Problem,
+        problem_type: Some("https://example.com/problems/".into()),
+    };
+    let res = Res::from(AppError::new(ErrorKind::NotFound, "用户不存在").with_extension("id", 7));
+    assert_eq!(
+        serde_json::to_value(Problem::new(&res, &config)).unwrap(),
+        serde_json::json!({
+            "type": "https://example.com/problems/not_found",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "用户不存在",
+            "code": "not_found",
+            "id": 7,
+        })
+    );
+}
@@ -1,12 +1,10 @@
-use std::fmt::Write;
-
 use axum::{
     async_trait,
     extract::{
         rejection::{BytesRejection, RawFormRejection},
         FromRequest, RawForm, Request,
     },
-    http::HeaderMap,
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
     RequestExt,
 };
 use axum_extra::headers::{ContentType, HeaderMapExt};
@@ -16,7 +14,11 @@ use once_cell::sync::Lazy;
 use serde::Deserialize;
 use validator::Validate;
 
-use crate::{reject, res, resp::Res, tools::parse_query};
+use crate::{
+    reject, res,
+    resp::{AppError, ErrorKind, Format, Res},
+    tools::parse_query,
+};
 
 /// 提取 Json 类型数据 并验证数据
 #[must_use]
@@ -41,6 +43,40 @@ where
     }
 }
 
+/// 按 Content-Type 提取 Json、MessagePack、CBOR 类型数据 并验证数据
+///
+/// 支持的格式见 [`Format`]
+#[must_use]
+#[derive(Debug, Clone, Default, Deref, DerefMut)]
+pub struct VBody<T: Validate>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for VBody<T>
+where
+    T: for<'de> Deserialize<'de> + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Res<()>;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(Format::from_content_type);
+        let Some(format) = format else {
+            return reject!(415, "不支持的请求体格式");
+        };
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|err| body_rejection(err.status(), err.body_text()))?;
+        let data = format.decode::<T>(&bytes).map_err(|err| res!(422, "{err}"))?;
+        validate(&data)?;
+        Ok(VBody(data))
+    }
+}
+
 /// 提取 Form 类型数据 并验证数据
 #[must_use]
 #[derive(Debug, Clone, Default, Deref, DerefMut)]
@@ -116,7 +152,7 @@ fn des_json<T>(data: Result<Bytes, BytesRejection>) -> Result<T, Res<()>>
 where
     T: for<'de> Deserialize<'de> + Validate,
 {
-    let bytes = data.map_err(|err| res!(422, "{err}"))?;
+    let bytes = data.map_err(|err| body_rejection(err.status(), err.body_text()))?;
     let data = serde_json::from_slice(&bytes).map_err(|err| res!(422, "{err}"))?;
 
     validate(&data)?;
@@ -129,31 +165,25 @@ where
     T: for<'de> Deserialize<'de> + Validate,
 {
     let data = match data {
-        Ok(RawForm(bytes)) => serde_urlencoded::from_bytes::<T>(&bytes)?,
-        Err(_) => return Err(res!(422, "无法获取到表单数据")),
+        Ok(RawForm(bytes)) => serde_urlencoded::from_bytes::<T>(&bytes).map_err(|err| res!(422, "{err}"))?,
+        Err(err) => return Err(body_rejection(err.status(), err.body_text())),
     };
 
     validate(&data)?;
     Ok(data)
 }
 
+/// 读取请求体失败 保留原来的状态码, 超过大小限制时为 413
+fn body_rejection(status: StatusCode, text: String) -> Res<()> {
+    match status {
+        StatusCode::PAYLOAD_TOO_LARGE => AppError::new(ErrorKind::PayloadTooLarge, text).into(),
+        status => res!(status.as_u16(), "{text}"),
+    }
+}
+
 /// 数据验证
 pub fn validate(data: impl Validate) -> Result<(), Res<()>> {
-    if let Err(err) = data.validate() {
-        let mut msg = String::new();
-        write!(msg, "数据验证失败: ").unwrap();
-        for (key, value) in err.field_errors() {
-            write!(msg, "{key}<").unwrap();
-            value
-                .iter()
-                .map(|m| &m.code)
-                .for_each(|field| write!(msg, "{field}, ").unwrap());
-            msg.replace_range(msg.len() - 2.., ">; ")
-        }
-        msg.pop();
-        return reject!(422, "{msg}");
-    }
-    Ok(())
+    Ok(data.validate()?)
 }
 
 #[test]
@@ -170,3 +200,20 @@ fn validate_t() {
     let user = User { email: "asd", age: 150 };
     println!("{}", serde_json::to_string(&validate(user).err().unwrap()).unwrap())
 }
+
+#[tokio::test]
+async fn body_limit_t() {
+    #[derive(Deserialize, Validate)]
+    struct User {}
+
+    // 超过默认的 2MB 限制
+    let req = Request::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(vec![b' '; 3 << 20]))
+        .unwrap();
+    let res = VBody::<User>::from_request(req, &()).await.err().unwrap();
+    assert_eq!(
+        (res.status, res.error),
+        (StatusCode::PAYLOAD_TOO_LARGE, Some("payload_too_large"))
+    );
+}
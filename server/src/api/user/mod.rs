@@ -1,16 +1,54 @@
 use library::{
-    jsonwebtoken::{Jwt, JwtToken},
-    reject, resolve,
-    resp::Resp,
-    validator::VJson,
+    jsonwebtoken::{Jwt, JwtClaims, JwtToken, RequireRole, Role, TokenPair},
+    resolve,
+    resp::{AppError, ErrorKind, Resp},
+    validator::VBody,
 };
+use serde::Deserialize;
+use validator::Validate;
 
 use crate::auth::jwt;
 
-pub async fn login(VJson(user): VJson<jwt::User>) -> Resp<String> {
-    match user.encode() {
-        Ok(token) => resolve!(201 => token, "登录成功"),
-        Err(err) => reject!(400, "登录失败: {err}"),
+#[derive(Debug, Deserialize, Validate)]
+pub struct LoginForm {
+    pub name: String,
+    pub phone: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RefreshForm {
+    #[validate(length(min = 1, code = "不能为空"))]
+    pub refresh_token: String,
+}
+
+pub async fn login(VBody(form): VBody<LoginForm>) -> Resp<TokenPair> {
+    let user = jwt::User {
+        name: form.name,
+        phone: form.phone,
+        roles: vec![jwt::Member::NAME.into()],
+    };
+    tracing::info!(name = %user.name, "用户登录");
+    match user.encode_pair().await {
+        Ok(pair) => resolve!(201 => pair, "登录成功"),
+        Err(err) => Err(AppError::new(ErrorKind::Internal, "登录失败").with_cause(err).into()),
+    }
+}
+
+pub async fn refresh(VBody(form): VBody<RefreshForm>) -> Resp<TokenPair> {
+    match jwt::User::refresh(&form.refresh_token).await {
+        Ok(pair) => resolve!(200 => pair, "刷新成功"),
+        Err(err) => Err(AppError::new(ErrorKind::Unauthorized, "刷新失败: 请重新登录")
+            .with_cause(err)
+            .into()),
+    }
+}
+
+pub async fn logout(JwtClaims(claims): JwtClaims<jwt::User>) -> Resp<()> {
+    match claims.revoke().await {
+        Ok(_) => resolve!(200, "退出登录成功"),
+        Err(err) => Err(AppError::new(ErrorKind::Internal, "退出登录失败")
+            .with_cause(err)
+            .into()),
     }
 }
 
@@ -18,6 +56,6 @@ pub async fn get_info(Jwt(user): Jwt<jwt::User>) -> Resp<jwt::User> {
     resolve!(200 => user, "获取用户信息成功")
 }
 
-pub async fn put_info(Jwt(user): Jwt<jwt::User>) -> Resp<jwt::User> {
+pub async fn put_info(RequireRole(user, _): RequireRole<jwt::User, jwt::Member>) -> Resp<jwt::User> {
     resolve!(200 => user, "修改用户信息成功")
 }
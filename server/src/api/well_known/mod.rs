@@ -0,0 +1,9 @@
+use axum::Json;
+use jsonwebtoken::jwk::JwkSet;
+
+use crate::config::CONFIG;
+
+/// 公钥集合 供其他服务验证令牌
+pub async fn jwks() -> Json<JwkSet> {
+    Json(CONFIG.jwt.secret.jwks())
+}
@@ -1,4 +1,4 @@
-use library::jsonwebtoken::{JwtToken, Secret};
+use library::jsonwebtoken::{Authority, JwtToken, Role, Secret};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -8,10 +8,45 @@ use crate::config::CONFIG;
 pub struct User {
     pub name: String,
     pub phone: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl Authority for User {
+    fn roles(&self) -> &[String] {
+        &self.roles
+    }
+}
+
+/// 普通用户
+pub struct Member;
+
+impl Role for Member {
+    const NAME: &'static str = "member";
 }
 
 impl JwtToken for User {
     fn secret() -> &'static Secret {
         &CONFIG.jwt.secret
     }
+
+    fn duration() -> usize {
+        CONFIG.jwt.duration as usize
+    }
+
+    fn refresh_duration() -> usize {
+        CONFIG.jwt.refresh as usize
+    }
+
+    fn issuer() -> Option<&'static str> {
+        CONFIG.jwt.issuer.as_deref()
+    }
+
+    fn audience() -> Option<&'static str> {
+        CONFIG.jwt.audience.as_deref()
+    }
+
+    fn leeway() -> u64 {
+        CONFIG.jwt.leeway
+    }
 }
@@ -1,22 +1,118 @@
-use std::fmt::{Debug, Formatter};
+use std::{
+    fmt::{Debug, Formatter},
+    path::PathBuf,
+};
 
-use library::jsonwebtoken::Secret;
-use serde::{Deserialize, Deserializer};
+use anyhow::bail;
+use jsonwebtoken::Algorithm;
+use library::jsonwebtoken::{JwtKey, Secret};
+use serde::Deserialize;
 
 #[derive(Clone, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[serde(try_from = "JwtFile")]
 pub struct JwtConfig {
-    #[serde(deserialize_with = "parse_secret")]
     pub secret: Secret,
     pub duration: u64,
+    pub refresh: u64,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    pub leeway: u64,
+    pub renew: Option<u64>,
+}
+
+/// 配置文件中的 [jwt]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JwtFile {
+    /// 签发令牌使用的 kid 只有一个秘钥时可以省略
+    active: Option<String>,
+    keys: Vec<KeyFile>,
+    duration: u64,
+    #[serde(default = "default_refresh")]
+    refresh: u64,
+    /// 签发者
+    issuer: Option<String>,
+    /// 受众 只接受为本服务签发的令牌
+    audience: Option<String>,
+    /// 允许的时钟误差(秒)
+    #[serde(default = "default_leeway")]
+    leeway: u64,
+    /// 剩余有效期少于该值(秒)时滑动续期 不配置时不续期
+    renew: Option<u64>,
+}
+
+/// 配置文件中的 [[jwt.keys]]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyFile {
+    kid: Option<String>,
+    /// 签名算法 默认 HS256
+    #[serde(default)]
+    algorithm: Algorithm,
+    /// 对称算法秘钥
+    secret: Option<String>,
+    /// 非对称算法私钥 不配置时只能验证令牌
+    private_key: Option<PathBuf>,
+    /// 非对称算法公钥 后缀为 .der 时按 DER 格式读取 否则按 PEM 格式
+    public_key: Option<PathBuf>,
+}
+
+library::gen_default! {
+    default_refresh, 60 * 60 * 24 * 30, u64;
+    default_leeway, 60, u64;
+}
+
+impl TryFrom<JwtFile> for JwtConfig {
+    type Error = String;
+
+    fn try_from(file: JwtFile) -> Result<Self, Self::Error> {
+        let secret = parse_secret(file.keys, file.active).map_err(|err| format!("加载 jwt 秘钥失败: {err}"))?;
+        Ok(Self {
+            secret,
+            duration: file.duration,
+            refresh: file.refresh,
+            issuer: file.issuer,
+            audience: file.audience,
+            leeway: file.leeway,
+            renew: file.renew,
+        })
+    }
+}
+
+fn parse_secret(keys: Vec<KeyFile>, active: Option<String>) -> anyhow::Result<Secret> {
+    let mut keys = keys.into_iter().map(parse_key).collect::<anyhow::Result<Vec<_>>>()?;
+    match active {
+        Some(active) => Secret::keyset(keys, &active),
+        None if keys.len() == 1 => Ok(Secret::from(keys.remove(0))),
+        None => bail!("配置了多个秘钥时需要指定 active"),
+    }
 }
 
-fn parse_secret<'de, D: Deserializer<'de>>(de: D) -> Result<Secret, D::Error> {
-    Ok(Secret::new(&String::deserialize(de)?))
+fn parse_key(file: KeyFile) -> anyhow::Result<JwtKey> {
+    let key = match (&file.secret, &file.public_key) {
+        (_, Some(public)) if public.extension().is_some_and(|ext| ext == "der") => {
+            JwtKey::from_der_file(file.algorithm, file.private_key.as_ref(), public)?
+        }
+        (_, Some(public)) => JwtKey::from_pem_file(file.algorithm, file.private_key.as_ref(), public)?,
+        (Some(secret), None) => JwtKey::hmac(file.algorithm, secret.as_bytes())?,
+        (None, None) => bail!("需要配置 secret 或 public_key"),
+    };
+    Ok(match file.kid {
+        Some(kid) => key.kid(kid),
+        None => key,
+    })
 }
 
 impl Debug for JwtConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Jwt").field("duration", &self.duration).finish()
+        f.debug_struct("Jwt")
+            .field("algorithm", &self.secret.algorithm())
+            .field("duration", &self.duration)
+            .field("refresh", &self.refresh)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("leeway", &self.leeway)
+            .field("renew", &self.renew)
+            .finish()
     }
 }
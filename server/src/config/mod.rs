@@ -3,7 +3,11 @@ library::re_export! {
    mod jwt;
 }
 
-use library::{config::ConfigLoad, logger::LoggerConfig};
+use library::{
+    config::ConfigLoad,
+    logger::LoggerConfig,
+    resp::{PageConfig, ResConfig},
+};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 
@@ -12,10 +16,14 @@ pub static CONFIG: Lazy<Config> = Lazy::new(|| Config::from_toml("config.toml",
 #[derive(Debug, Deserialize)]
 pub struct Config {
     #[cfg(feature = "database")]
-    pub database: crate::database::PgConfig,
+    pub database: library::database::postgres::PgConfig,
     pub logger: LoggerConfig,
     pub server: ServerConfig,
     pub jwt: JwtConfig,
+    #[serde(default)]
+    pub response: ResConfig,
+    #[serde(default)]
+    pub page: PageConfig,
 }
 
 impl ConfigLoad for Config {}
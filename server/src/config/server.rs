@@ -1,5 +1,7 @@
 use std::net::SocketAddr;
 
+use ipnet::IpNet;
+use library::middleware::ForwardedHeader;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -8,6 +10,12 @@ pub struct ServerConfig {
     pub addr: SocketAddr,
     #[serde(default = "default_protocol")]
     pub protocol: String,
+    /// 可信代理网段 只信任来自这些地址的 X-Forwarded-For 等请求头
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNet>,
+    /// 可信代理写入客户端地址的请求头 x-forwarded-for forwarded x-real-ip
+    #[serde(default)]
+    pub forwarded_header: ForwardedHeader,
 }
 
 library::gen_default!(default_protocol, "http");
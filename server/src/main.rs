@@ -1,12 +1,19 @@
 use std::net::SocketAddr;
 
-use library::tools;
+use library::{logger::Logger, tools};
 use server::{config::CONFIG, router};
 use tokio::net::TcpListener;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() {
-    let app = router::router().await;
+    let logger = Logger::new(CONFIG.logger.clone());
+    let logger_handle = logger.handle();
+    tracing_subscriber::registry().with(logger.tracing_layer()).init();
+    CONFIG.response.clone().init();
+    CONFIG.page.clone().init();
+
+    let app = router::router(logger).await;
     tools::print_router_info(&app);
 
     let addr = &CONFIG.server.addr;
@@ -14,6 +21,32 @@ async fn main() {
 
     let listener = TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
+
+    // 写完剩余日志再退出
+    logger_handle.shutdown();
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("监听 Ctrl+C 失败");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("监听 SIGTERM 失败")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
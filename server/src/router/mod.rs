@@ -4,18 +4,23 @@ use axum::{routing::get, Router};
 use library::{
     interceptor::{Download, Html404},
     logger::Logger,
+    middleware::{ClientIpLayer, NegotiateLayer, RequestIdLayer},
 };
 use tower_http::services::ServeDir;
 
-use crate::config::CONFIG;
+use crate::{api::well_known, config::CONFIG};
 
-pub async fn router() -> Router {
+pub async fn router(logger: Logger) -> Router {
     Router::new()
         .merge(static_server())
         .route("/", get(|| async { "hello world" }))
+        .route("/.well-known/jwks.json", get(well_known::jwks))
         .nest("/user", user::router().await)
+        .layer(NegotiateLayer)
         .layer(Html404::new("static/404.html"))
-        .layer(Logger::new(CONFIG.logger.clone()))
+        .layer(logger)
+        .layer(ClientIpLayer::new(CONFIG.server.trusted_proxies.clone()).header(CONFIG.server.forwarded_header))
+        .layer(RequestIdLayer)
 }
 
 fn static_server() -> Router {
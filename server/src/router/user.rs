@@ -1,14 +1,32 @@
 use axum::{
+    http::{HeaderName, Method},
     routing::{get, post},
     Router,
 };
-use library::jsonwebtoken::JwtAuth;
+use library::{
+    compare::{Rule, Rules},
+    jsonwebtoken::{JwtAuth, Renew},
+};
 
-use crate::{api::user, auth::jwt};
+use crate::{api::user, auth::jwt, config::CONFIG};
 
 pub async fn router() -> Router {
+    let allow = Rules::from_iter([
+        Rule::exact("/login").method(Method::POST),
+        Rule::exact("/refresh").method(Method::POST),
+    ]);
+    let mut auth = JwtAuth::<jwt::User, _>::new(allow);
+    if let Some(window) = CONFIG.jwt.renew {
+        auth = auth.renew(
+            window as usize,
+            Renew::Header(HeaderName::from_static("x-renewed-token")),
+        );
+    }
+
     Router::new()
         .route("/login", post(user::login))
+        .route("/refresh", post(user::refresh))
+        .route("/logout", post(user::logout))
         .route("/info", get(user::get_info).put(user::put_info))
-        .layer(JwtAuth::<jwt::User, _>::new("/login"))
+        .layer(auth)
 }